@@ -0,0 +1,38 @@
+//! Times pairing a large pool of random players.
+//!
+//! `cargo run --release --example bench -- [players] [solver]`, e.g. `-- 300 greedy`
+
+use std::time::Instant;
+
+use omega_strikers_pairing::{generate::generate_players, Pairer, PairingOptions, Solver};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+
+    let count: usize = args.next().map(|x| x.parse().unwrap()).unwrap_or(300);
+    let solver: Solver = args.next().map(|x| x.parse().unwrap()).unwrap_or_default();
+
+    let players = generate_players(count, &mut StdRng::seed_from_u64(0));
+
+    let options = PairingOptions {
+        solver,
+        seed: Some(0),
+        ..Default::default()
+    };
+
+    let pairer = Pairer::new(players, options);
+
+    let started = Instant::now();
+
+    let result = pairer.pair().unwrap();
+
+    println!(
+        "Paired {} players into {} teams with the {} solver in {:?}, score {:.2}",
+        count,
+        result.teams.len(),
+        solver,
+        started.elapsed(),
+        result.score(pairer.target_rank(), &result.config)
+    );
+}
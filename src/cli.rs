@@ -0,0 +1,209 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::{Args, Parser, Subcommand};
+use omega_strikers_pairing::{
+    pairer::DEFAULT_TIME_LIMIT, solver::annealing::AnnealingOptions, Solver, SubstitutePolicy,
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "A super quick pairing algorithm for omega strikers tournaments"
+)]
+pub struct Cli {
+    /// Don't wait for a key press before exiting, for scripts and CI
+    #[arg(long, global = true)]
+    pub no_pause: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Pairs the players into named teams (the default)
+    Pair(PairArgs),
+    /// Checks that a players file can be loaded
+    Validate(ValidateArgs),
+    /// Scores an existing teams file
+    Score(ScoreArgs),
+    /// Generates new names for the teams in an existing teams file, locked teams keep theirs
+    Name(NameArgs),
+    /// Generates random players, for testing
+    Generate(GenerateArgs),
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Command::Pair(PairArgs::default())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PairArgs {
+    /// Players to pair
+    #[arg(short, long, default_value = "players.json")]
+    pub players: PathBuf,
+
+    /// Teams formed before pairing, in addition to a `locked` section in the players file
+    #[arg(short, long)]
+    pub locked_teams: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub words: WordArgs,
+
+    /// Scoring config (toml or json), defaults are used if not given
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// Algorithm to pick teams with: greedy, exact or annealing
+    #[arg(short, long, default_value = "greedy")]
+    pub solver: Solver,
+
+    /// How many seconds the exact solver may search for before using the best teams found so far
+    #[arg(long, default_value_t = DEFAULT_TIME_LIMIT.as_secs_f64(), value_parser = parse_seconds)]
+    pub time_limit: f64,
+
+    /// How many times the annealing solver starts over from a random split
+    #[arg(long, default_value_t = AnnealingOptions::default().restarts)]
+    pub restarts: usize,
+
+    /// How many swaps the annealing solver tries per restart
+    #[arg(long, default_value_t = AnnealingOptions::default().iterations)]
+    pub iterations: usize,
+
+    /// Seed for all randomness, the same players and seed always give the same output
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// How many of the best role assignments of each trio of players the greedy solver considers (1-6)
+    #[arg(long, default_value_t = 1)]
+    pub assignments_per_trio: usize,
+
+    /// Who sits out when the number of players isn't a multiple of three: lowest-impact or volunteers
+    #[arg(long, default_value = "lowest-impact")]
+    pub substitutes: SubstitutePolicy,
+
+    /// How many threads to score teams on, defaults to all cores. Needs the parallel feature, ignored with a warning otherwise
+    #[arg(long)]
+    pub threads: Option<usize>,
+
+    /// Don't try to improve the picked teams by swapping players between them
+    #[arg(long)]
+    pub no_local_search: bool,
+
+    /// Where to write the teams
+    #[arg(short, long, default_value = "output_teams.json")]
+    pub output: PathBuf,
+}
+
+impl Default for PairArgs {
+    fn default() -> Self {
+        Self {
+            players: "players.json".into(),
+            locked_teams: None,
+            words: WordArgs::default(),
+            config: None,
+            solver: Solver::default(),
+            time_limit: DEFAULT_TIME_LIMIT.as_secs_f64(),
+            restarts: AnnealingOptions::default().restarts,
+            iterations: AnnealingOptions::default().iterations,
+            seed: None,
+            assignments_per_trio: 1,
+            substitutes: SubstitutePolicy::default(),
+            threads: None,
+            no_local_search: false,
+            output: "output_teams.json".into(),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    /// Players to validate
+    #[arg(short, long, default_value = "players.json")]
+    pub players: PathBuf,
+
+    /// Scoring config (toml or json), to check hard blacklists against
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// Exit with an error if there are any problems
+    #[arg(long)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ScoreArgs {
+    /// Teams to score
+    #[arg(short, long, default_value = "output_teams.json")]
+    pub teams: PathBuf,
+
+    /// Scoring config (toml or json), defaults to the config stored in the teams file
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct NameArgs {
+    /// Teams to rename
+    #[arg(short, long, default_value = "output_teams.json")]
+    pub teams: PathBuf,
+
+    #[command(flatten)]
+    pub words: WordArgs,
+
+    /// Seed for the names, the same teams and seed always give the same names
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Where to write the renamed teams, defaults to overwriting the input
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateArgs {
+    /// How many players to generate
+    #[arg(short = 'n', long, default_value_t = 30)]
+    pub count: usize,
+
+    /// Seed for the players, the same seed always gives the same players
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Where to write the players
+    #[arg(short, long, default_value = "players_generated.json")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct WordArgs {
+    /// Adjectives to generate team names from
+    #[arg(long, default_value = "adjectives.json")]
+    pub adjectives: PathBuf,
+
+    /// Nouns to generate team names from
+    #[arg(long, default_value = "nouns.json")]
+    pub nouns: PathBuf,
+}
+
+impl Default for WordArgs {
+    fn default() -> Self {
+        Self {
+            adjectives: "adjectives.json".into(),
+            nouns: "nouns.json".into(),
+        }
+    }
+}
+
+/// Parses a number of seconds, which can't be negative
+fn parse_seconds(s: &str) -> Result<f64, String> {
+    let seconds: f64 = s
+        .parse()
+        .map_err(|_| format!("'{}' is not a number of seconds", s))?;
+
+    Duration::try_from_secs_f64(seconds)
+        .map(|_| seconds)
+        .map_err(|_| format!("{} is not a number of seconds from 0 up", s))
+}
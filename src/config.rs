@@ -0,0 +1,386 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rank::Rank;
+
+pub const DEFAULT_ROLE_WEIGHT: f64 = 1.0;
+pub const DEFAULT_RANK_WEIGHT: f64 = 10.0;
+pub const DEFAULT_BLACKLIST_WEIGHT: f64 = 20.0;
+pub const DEFAULT_ALLOWED_RANK_DEVIATION: f64 = 5.0;
+pub const DEFAULT_PREFERRED_TEAMMATE_WEIGHT: f64 = 5.0;
+pub const DEFAULT_RANK_PENALTY_WEIGHT: f64 = 10.0;
+pub const DEFAULT_RATING_AT_RANK_ZERO: f64 = 0.0;
+pub const DEFAULT_RATING_PER_RANK: f64 = 100.0;
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+/// How blacklisted players are kept apart
+pub enum BlacklistMode {
+    /// Every pair of players on a team where one hates the other costs `blacklist_weight`
+    #[default]
+    Soft,
+    /// Players are never put on a team with someone who hates them, or whom they hate
+    Hard,
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+/// What a player's strength is measured in
+pub enum StrengthUnit {
+    /// Their rank, ratings are ignored
+    #[default]
+    Rank,
+    /// Their rating, players without one get a rating based on their rank
+    Rating,
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+/// How the rank score of a team falls off as its strength gets further from the target
+///
+/// All curves give the full `rank_weight` at exactly the target rank.
+pub enum RankCurve {
+    /// Falls off in a straight line, to nothing at `allowed_rank_deviation` and beyond
+    #[default]
+    Linear,
+    /// Falls off slowly near the target and faster further away, reaching nothing at
+    /// `allowed_rank_deviation` and going negative beyond it
+    Quadratic,
+    /// A bell curve, which is down to about a third at `allowed_rank_deviation` and keeps
+    /// shrinking towards nothing, so being further off always counts
+    Gaussian,
+    /// Falls off like linear, but beyond `allowed_rank_deviation` every further
+    /// `allowed_rank_deviation` costs `rank_penalty_weight`
+    Hinge,
+}
+
+impl RankCurve {
+    /// Returns how much of the rank weight a team gets, given how far it is off the target
+    /// and how far it may be. 1 at the target, negative for curves which penalize
+    pub fn multiplier(self, rank_diff: f64, allowed_deviation: f64) -> f64 {
+        let allowed_deviation = allowed_deviation.max(f64::EPSILON);
+        let x = rank_diff.abs() / allowed_deviation;
+
+        match self {
+            RankCurve::Linear => (1.0 - x).max(0.0),
+            RankCurve::Quadratic => 1.0 - x * x,
+            RankCurve::Gaussian => (-x * x).exp(),
+            RankCurve::Hinge => 1.0 - x,
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+/// What the solvers try to maximize, given the scores of all teams
+pub enum Objective {
+    /// The summed score of all teams
+    #[default]
+    Sum,
+    /// The score of the worst team, times the number of teams. Where that ties, the local search
+    /// and the annealing solver prefer the higher summed score
+    MaxMin,
+    /// The summed score, minus a penalty for how far team strengths are from their average.
+    /// Every team `allowed_rank_deviation` off the average costs `rank_weight`, growing
+    /// quadratically
+    MinVariance,
+}
+
+impl Display for Objective {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Objective::Sum => write!(f, "sum"),
+            Objective::MaxMin => write!(f, "max-min"),
+            Objective::MinVariance => write!(f, "min-variance"),
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+/// When a [scoring rule](ScoringRule) applies to a team, based on the values the players have
+/// for its attribute
+pub enum RuleKind {
+    /// Once if all three players share a value, e.g. a language
+    #[default]
+    Shared,
+    /// Once for every pair of players who share a value, so two out of three counts too
+    Pairs,
+    /// Once if at least two players have the attribute and no two share a value, e.g. to spread
+    /// out players from the same club
+    Distinct,
+    /// Once if the players don't all have the same values, e.g. new players with experienced ones
+    Mixed,
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// A user-defined term of the team score, based on a player attribute
+pub struct ScoringRule {
+    /// Shown in the score breakdown
+    pub name: String,
+    /// The attribute of the players the rule looks at
+    pub attribute: String,
+    #[serde(default)]
+    pub kind: RuleKind,
+    /// How much is added every time the rule applies, negative to subtract
+    pub weight: f64,
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
+#[serde(default)]
+/// How player ratings (e.g. LP or elo) are taken into account
+pub struct RatingConfig {
+    /// Whether strength is measured in ranks or ratings. `allowed_rank_deviation` is in the
+    /// same unit
+    pub unit: StrengthUnit,
+    /// The rating of a player at rank 0 without a rating
+    pub rating_at_rank_zero: f64,
+    /// How much rating every rank above 0 adds, for players without a rating
+    pub rating_per_rank: f64,
+}
+
+impl Default for RatingConfig {
+    fn default() -> Self {
+        Self {
+            unit: StrengthUnit::default(),
+            rating_at_rank_zero: DEFAULT_RATING_AT_RANK_ZERO,
+            rating_per_rank: DEFAULT_RATING_PER_RANK,
+        }
+    }
+}
+
+impl RatingConfig {
+    /// Converts a number of ranks into the configured unit
+    pub fn in_unit(&self, ranks: f64) -> f64 {
+        match self.unit {
+            StrengthUnit::Rank => ranks,
+            StrengthUnit::Rating => ranks * self.rating_per_rank,
+        }
+    }
+
+    /// Returns how strong a player is, in the configured unit
+    pub fn strength(&self, rank: Rank, rating: Option<f64>) -> f64 {
+        match self.unit {
+            StrengthUnit::Rank => f64::from(rank),
+            StrengthUnit::Rating => rating.unwrap_or_else(|| {
+                self.rating_at_rank_zero + f64::from(rank) * self.rating_per_rank
+            }),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
+#[serde(default)]
+/// The weights used when scoring teams
+///
+/// Any value missing from a config file falls back to its default.
+pub struct ScoringConfig {
+    /// How much a player's preference for their role is worth
+    pub role_weight: f64,
+    /// How much a team being at exactly the target rank is worth
+    pub rank_weight: f64,
+    /// How much is subtracted from a team for every pair of players who hate eachother
+    pub blacklist_weight: f64,
+    /// Whether blacklists only cost score, or are never broken
+    pub blacklist_mode: BlacklistMode,
+    /// How far off the target rank a team may be before it gets no rank score, in the unit of
+    /// `rating.unit`. Defaults to [`DEFAULT_ALLOWED_RANK_DEVIATION`] ranks, in rating points
+    /// with ratings, see [`allowed_deviation`](Self::allowed_deviation)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_rank_deviation: Option<f64>,
+    /// How the rank score falls off as a team gets further from the target rank
+    pub rank_curve: RankCurve,
+    /// How much is subtracted for every `allowed_rank_deviation` a team is beyond it, with the
+    /// hinge curve
+    pub rank_penalty_weight: f64,
+    /// How much is added for every player on a team with someone they would like to play with
+    pub preferred_teammate_weight: f64,
+    /// What the solvers maximize
+    pub objective: Objective,
+    /// Whether team strength is measured in ranks or ratings
+    pub rating: RatingConfig,
+    /// Extra terms of the team score, e.g. a bonus for teams which share a language
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ScoringRule>,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            role_weight: DEFAULT_ROLE_WEIGHT,
+            rank_weight: DEFAULT_RANK_WEIGHT,
+            blacklist_weight: DEFAULT_BLACKLIST_WEIGHT,
+            blacklist_mode: BlacklistMode::default(),
+            allowed_rank_deviation: None,
+            rank_curve: RankCurve::default(),
+            rank_penalty_weight: DEFAULT_RANK_PENALTY_WEIGHT,
+            preferred_teammate_weight: DEFAULT_PREFERRED_TEAMMATE_WEIGHT,
+            objective: Objective::default(),
+            rating: RatingConfig::default(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl ScoringConfig {
+    /// Parses a config from a toml string
+    pub fn from_toml_str(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
+    }
+
+    /// Parses a config from a json string
+    pub fn from_json_str(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// Returns the config with every value it falls back to filled in, e.g. to write down
+    /// exactly what teams were scored with
+    pub fn resolved(&self) -> Self {
+        Self {
+            allowed_rank_deviation: Some(self.allowed_deviation()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns how far off the target rank a team may be, in the unit of `rating.unit`
+    pub fn allowed_deviation(&self) -> f64 {
+        self.allowed_rank_deviation
+            .unwrap_or_else(|| self.rating.in_unit(DEFAULT_ALLOWED_RANK_DEVIATION))
+    }
+
+    /// Returns whether strength is measured in ratings, but `allowed_rank_deviation` is less
+    /// than a single rank, so hardly any team gets a rank score
+    pub fn deviation_below_a_rank(&self) -> bool {
+        self.rating.unit == StrengthUnit::Rating
+            && self.allowed_deviation() < self.rating.in_unit(1.0)
+    }
+
+    /// Returns the rank score of a team, given how far its strength is off the target
+    pub fn rank_score(&self, rank_diff: f64) -> f64 {
+        let multiplier = self
+            .rank_curve
+            .multiplier(rank_diff, self.allowed_deviation());
+
+        match self.rank_curve {
+            // Past the edge, the hinge penalty has its own weight
+            RankCurve::Hinge if multiplier < 0.0 => multiplier * self.rank_penalty_weight,
+            _ => multiplier * self.rank_weight,
+        }
+    }
+
+    /// Combines the scores of teams into the value of the objective
+    ///
+    /// `strengths` are the strengths of the same teams, i.e. the summed strengths of their players.
+    pub fn objective_value(&self, scores: &[f64], strengths: &[f64]) -> f64 {
+        let score_sum: f64 = scores.iter().sum();
+
+        match self.objective {
+            Objective::Sum => score_sum,
+            Objective::MaxMin => match scores.iter().copied().reduce(f64::min) {
+                Some(min) => min * scores.len() as f64,
+                None => 0.0,
+            },
+            Objective::MinVariance => {
+                let strength_sum: f64 = strengths.iter().sum();
+                let square_sum: f64 = strengths.iter().map(|x| x * x).sum();
+
+                score_sum - self.variance_penalty(strengths.len(), strength_sum, square_sum)
+            }
+        }
+    }
+
+    /// Returns what the spread of team strengths costs with [`Objective::MinVariance`], given
+    /// the number of teams and the sum and summed squares of their strengths
+    pub fn variance_penalty(&self, count: usize, strength_sum: f64, square_sum: f64) -> f64 {
+        if count == 0 {
+            return 0.0;
+        }
+
+        // The summed squared distances from the average
+        let squared_deviation = (square_sum - strength_sum * strength_sum / count as f64).max(0.0);
+
+        let allowed_deviation = self.allowed_deviation().max(f64::EPSILON);
+
+        self.rank_weight * squared_deviation / (allowed_deviation * allowed_deviation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A config with the given curve, where the rank and hinge penalty weights differ
+    fn with_curve(rank_curve: RankCurve) -> ScoringConfig {
+        ScoringConfig {
+            rank_curve,
+            rank_weight: 10.0,
+            rank_penalty_weight: 4.0,
+            allowed_rank_deviation: Some(5.0),
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} vs {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn rank_curves_at_the_target_and_the_edges() {
+        // Rank scores at no deviation, at allowed_rank_deviation and at twice that
+        for (curve, expected) in [
+            (RankCurve::Linear, [10.0, 0.0, 0.0]),
+            (RankCurve::Quadratic, [10.0, 0.0, -30.0]),
+            (
+                RankCurve::Gaussian,
+                [10.0, 10.0 * (-1.0_f64).exp(), 10.0 * (-4.0_f64).exp()],
+            ),
+            (RankCurve::Hinge, [10.0, 0.0, -4.0]),
+        ] {
+            let config = with_curve(curve);
+
+            for (deviation, expected) in [0.0, 5.0, 10.0].into_iter().zip(expected) {
+                // Too weak or too strong doesn't matter
+                assert_close(config.rank_score(deviation), expected);
+                assert_close(config.rank_score(-deviation), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn hinge_penalty_grows_with_its_own_weight() {
+        let config = with_curve(RankCurve::Hinge);
+
+        // Within the edge it falls off like linear, with the rank weight
+        assert_close(config.rank_score(2.5), 5.0);
+        assert_close(
+            config.rank_score(2.5),
+            with_curve(RankCurve::Linear).rank_score(2.5),
+        );
+
+        // Past it every further allowed_rank_deviation costs rank_penalty_weight
+        for deviation in [5.5, 7.5, 10.0, 15.0] {
+            let score = config.rank_score(deviation);
+
+            assert!(score < 0.0);
+            assert_close(score, -(deviation - 5.0) / 5.0 * 4.0);
+        }
+
+        assert_close(config.rank_score(12.5) - config.rank_score(7.5), -4.0);
+    }
+}
@@ -0,0 +1,36 @@
+use rand::Rng;
+
+use crate::types::StringPlayer;
+
+/// The chance for each generated player to hate another random player
+pub const BLACKLIST_CHANCE: f64 = 0.1;
+
+/// Generates random players, for testing the pairing.
+///
+/// Some players randomly hate eachother.
+pub fn generate_players<R: Rng>(count: usize, rng: &mut R) -> Vec<StringPlayer> {
+    let mut players = Vec::new();
+
+    for _i in 0..count {
+        players.push(StringPlayer::create_random(rng));
+    }
+
+    if players.is_empty() {
+        return players;
+    }
+
+    // Randomly make some players hate eachother
+    for player_index in 0..players.len() {
+        if rng.gen_bool(BLACKLIST_CHANCE) {
+            // pick a random person
+            let index = rng.gen_range(0..players.len());
+
+            let other_id = players[index].id.clone();
+            let current = players.get_mut(player_index).unwrap();
+
+            current.blacklisted_players.push(other_id);
+        }
+    }
+
+    players
+}
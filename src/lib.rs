@@ -0,0 +1,36 @@
+//! A super quick pairing algorithm for omega strikers tournaments.
+//!
+//! The engine is exposed through [`Pairer`], which takes a list of [`StringPlayer`]s and
+//! returns a [`Result`] with named teams.
+//!
+//! ```no_run
+//! use omega_strikers_pairing::{Pairer, PairingOptions};
+//!
+//! let players = serde_json::from_str(&std::fs::read_to_string("players.json").unwrap()).unwrap();
+//!
+//! let pairer = Pairer::new(players, PairingOptions::default());
+//! let result = pairer.pair().unwrap();
+//! ```
+
+pub mod config;
+pub mod generate;
+pub mod naming;
+pub mod pairer;
+pub mod rank;
+pub mod scoring;
+pub mod solver;
+pub mod summary;
+pub mod types;
+pub mod validate;
+
+pub use config::{BlacklistMode, Objective, RankCurve, RuleKind, ScoringConfig, ScoringRule};
+pub use pairer::{Pairer, PairingError, PairingOptions, SubstitutePolicy};
+pub use rank::Rank;
+pub use scoring::{PlayerLike, Scorer, TeamLike};
+pub use solver::Solver;
+pub use summary::Summary;
+pub use types::{
+    IndexTeam, LockedTeam, NamedTeam, Player, PlayersInput, Result, Role, RolePreferences,
+    RoleScore, RuleScore, ScoreBreakdown, StrengthSpread, StringPlayer, StringTeam, Team,
+    UnsatisfiedRequest,
+};
@@ -1,375 +1,512 @@
 use std::{
-    collections::HashSet,
-    fmt::Debug,
+    fmt::Display,
     io::{Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
 };
 
+use clap::Parser;
+use cli::{Cli, Command, GenerateArgs, NameArgs, PairArgs, ScoreArgs, ValidateArgs, WordArgs};
 use color_print::cprintln;
-use rand::Rng;
-use types::{NamedTeam, Player, Result, Role, StringPlayer, StringTeam, Team};
-use uuid::Uuid;
-
-mod types;
-
-pub const NUM_PLAYERS: usize = 30;
-
-fn main() {
-    let read_to_string_res = std::fs::read_to_string("players.json");
-
-    if let Err(ref e) = read_to_string_res {
-        println!("Failed to read players.json: {}", e);
-        pause();
-    }
-
-    let read_to_string = read_to_string_res.unwrap();
-
-    let players_res = serde_json::from_str(&read_to_string);
-
-    if let Err(ref e) = players_res {
-        println!("Failed to deserialize players.json: {}", e);
-        pause();
-    }
-
-    let string_players: Vec<StringPlayer> = players_res.unwrap();
-
-    let read_to_string_res = std::fs::read_to_string("adjectives.json");
+use omega_strikers_pairing::{
+    generate::generate_players, naming::name_teams, solver::annealing::AnnealingOptions,
+    validate::validate_players_file, LockedTeam, Objective, Pairer, PairingError, PairingOptions,
+    PlayersInput, ScoringConfig, StrengthSpread, StringPlayer, Summary,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{de::DeserializeOwned, Serialize};
+
+mod cli;
+
+#[derive(Debug)]
+/// An error which ends the program
+enum CliError {
+    Read(PathBuf, std::io::Error),
+    Deserialize(PathBuf, String),
+    Write(PathBuf, std::io::Error),
+    Pairing(PairingError),
+    Invalid(PathBuf, usize),
+}
 
-    if let Err(ref e) = read_to_string_res {
-        println!("Failed to read adjectives.json: {}", e);
-        pause();
+impl CliError {
+    /// The exit code we report for the error
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Read(..) => 3,
+            CliError::Deserialize(..) => 4,
+            CliError::Write(..) => 5,
+            CliError::Pairing(..) => 6,
+            CliError::Invalid(..) => 7,
+        }
     }
+}
 
-    let read_to_string = read_to_string_res.unwrap();
-
-    let team_name_adjectives_res = serde_json::from_str(&read_to_string);
-
-    if let Err(ref e) = team_name_adjectives_res {
-        println!("Failed to deserialize adjectives.json: {}", e);
-        pause();
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Read(path, e) => write!(f, "Failed to read {}: {}", path.display(), e),
+            CliError::Deserialize(path, e) => {
+                write!(f, "Failed to deserialize {}: {}", path.display(), e)
+            }
+            CliError::Write(path, e) => write!(f, "Failed to write {}: {}", path.display(), e),
+            CliError::Pairing(e) => write!(f, "{}", e),
+            CliError::Invalid(path, count) => {
+                write!(f, "Found {} problems in {}", count, path.display())
+            }
+        }
     }
+}
 
-    let team_name_adjectives_deserialized: Vec<String> = team_name_adjectives_res.unwrap();
-
-    let read_to_string_res = std::fs::read_to_string("nouns.json");
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let res = match cli.command.unwrap_or_default() {
+        Command::Pair(args) => pair(args),
+        Command::Validate(args) => validate(args),
+        Command::Score(args) => score(args),
+        Command::Name(args) => name(args),
+        Command::Generate(args) => generate(args),
+    };
+
+    let exit_code = match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(e.exit_code())
+        }
+    };
 
-    if let Err(ref e) = read_to_string_res {
-        println!("Failed to read nouns.json: {}", e);
+    if !cli.no_pause {
         pause();
     }
 
-    let read_to_string = read_to_string_res.unwrap();
+    exit_code
+}
 
-    let team_name_nouns_res = serde_json::from_str(&read_to_string);
+fn pair(args: PairArgs) -> Result<(), CliError> {
+    let (string_players, mut locked_teams) = read_players(&args.players)?;
+    let (adjectives, nouns) = read_words(&args.words)?;
 
-    if let Err(ref e) = team_name_nouns_res {
-        println!("Failed to deserialize nouns.json: {}", e);
-        pause();
+    if let Some(ref path) = args.locked_teams {
+        let more_locked_teams: Vec<LockedTeam> = read_json(path)?;
+        locked_teams.extend(more_locked_teams);
     }
 
-    let team_name_nouns_deserialized: Vec<String> = team_name_nouns_res.unwrap();
+    let scoring = match args.config {
+        Some(ref path) => read_config(path)?,
+        None => ScoringConfig::default(),
+    };
+
+    let options = PairingOptions {
+        scoring,
+        solver: args.solver,
+        time_limit: Some(Duration::from_secs_f64(args.time_limit)),
+        annealing: AnnealingOptions {
+            restarts: args.restarts,
+            iterations: args.iterations,
+            ..Default::default()
+        },
+        seed: args.seed,
+        local_search: !args.no_local_search,
+        assignments_per_trio: args.assignments_per_trio,
+        substitute_policy: args.substitutes,
+        threads: args.threads,
+        adjectives,
+        nouns,
+        verbose: true,
+        ..Default::default()
+    };
+
+    let pairer = Pairer::new(string_players, options).with_locked_teams(locked_teams);
 
-    // Cloning names is expensive, so only keep one instance of them while refering by
-    // index in other cases
-    let mut player_names = Vec::new();
-    let mut players = Vec::new();
+    let started = std::time::Instant::now();
 
-    for i in 0..string_players.len() {
-        let player = string_players.get(i).unwrap();
+    let result = pairer.pair().map_err(CliError::Pairing)?;
 
-        player_names.push(player.id.clone());
-    }
+    cprintln!("<cyan>Took {:?}</cyan>", started.elapsed());
 
-    for i in 0..string_players.len() {
-        let player = string_players.get(i).unwrap();
+    print_result(&result, pairer.target_rank(), &result.config);
 
-        let mut player_name_index = 0;
+    write_json(&args.output, &result)
+}
 
-        for j in 0..player_names.len() {
-            let random_player_name = player_names.get(j).unwrap();
+fn validate(args: ValidateArgs) -> Result<(), CliError> {
+    let path = &args.players;
 
-            if player.id.eq(random_player_name) {
-                player_name_index = j;
-            }
-        }
+    let read_to_string =
+        std::fs::read_to_string(path).map_err(|e| CliError::Read(path.to_path_buf(), e))?;
 
-        let mut blacklisted_players = Vec::new();
+    let config = match args.config {
+        Some(ref path) => read_config(path)?,
+        None => ScoringConfig::default(),
+    };
 
-        for blacklisted_player_id in player.blacklisted_players.clone().into_iter() {
-            let mut blacklisted_player_name_index = usize::MAX;
+    let deserialize_error =
+        |e: serde_json::Error| CliError::Deserialize(path.to_path_buf(), e.to_string());
 
-            for j in 0..player_names.len() {
-                let random_player_name = player_names.get(j).unwrap();
+    let issues = validate_players_file(&read_to_string, &config).map_err(deserialize_error)?;
 
-                if blacklisted_player_id
-                    .to_lowercase()
-                    .eq(&random_player_name.to_lowercase())
-                {
-                    blacklisted_player_name_index = j;
-                }
-            }
+    // Counted from the raw json, players with bad ranks are reported rather than failing
+    let value: serde_json::Value =
+        serde_json::from_str(&read_to_string).map_err(deserialize_error)?;
 
-            // We didn't find them player name
-            if blacklisted_player_name_index == usize::MAX {
-                println!(
-                    "Player {} hates {}, but the latter is not a registered player. Ignoring",
-                    player.id, blacklisted_player_id
-                );
-                continue;
-            }
+    let count = |x: Option<&serde_json::Value>| x.and_then(|x| x.as_array()).map_or(0, Vec::len);
 
-            blacklisted_players.push(blacklisted_player_name_index);
+    let (player_count, locked_count) = match value {
+        serde_json::Value::Object(ref object) => {
+            (count(object.get("players")), count(object.get("locked")))
         }
+        ref players => (count(Some(players)), 0),
+    };
 
-        let id_player = Player {
-            id: player_name_index,
-            blacklisted_players,
-            role_preferences: player.role_preferences,
-            rank: player.rank,
-        };
-
-        players.push(id_player);
+    for issue in issues.iter() {
+        cprintln!("<yellow>{}</yellow>", issue);
     }
 
-    /*
+    cprintln!(
+        "<green>Loaded {} players and {} locked teams from {}</green>",
+        player_count,
+        locked_count,
+        path.display()
+    );
 
-     If generating players:
+    if issues.is_empty() {
+        cprintln!("<green>No problems found</green>");
+    } else if args.strict {
+        return Err(CliError::Invalid(path.to_path_buf(), issues.len()));
+    } else {
+        cprintln!("<yellow>Found {} problems</yellow>", issues.len());
+    }
 
-    let mut players = Vec::new();
+    Ok(())
+}
 
-    for _i in 0..NUM_PLAYERS {
-        players.push(Player::create_random());
-    }
+fn score(args: ScoreArgs) -> Result<(), CliError> {
+    let result: omega_strikers_pairing::Result = read_json(&args.teams)?;
 
-    // Randomly make some players hate eachother
-    for player_index in 0..players.len() {
-        // 10 percent chance
-        let mut random = rand::thread_rng();
-        let random_number = random.gen_range(1..=10);
+    let config = match args.config {
+        Some(ref path) => read_config(path)?,
+        None => result.config.clone(),
+    };
 
-        if random_number == 1 {
-            // pick a random person
-            let index = random.gen_range(0..NUM_PLAYERS);
+    print_result(&result, result.target_rank(&config), &config);
 
-            let other_id = players[index].id.clone();
-            let current = players.get_mut(player_index).unwrap();
+    Ok(())
+}
 
-            current.blacklisted_players.push(other_id);
+fn name(args: NameArgs) -> Result<(), CliError> {
+    let mut result: omega_strikers_pairing::Result = read_json(&args.teams)?;
+    let (adjectives, nouns) = read_words(&args.words)?;
+
+    // Locked teams keep their names, only the others get new ones
+    let teams = result
+        .teams
+        .iter()
+        .filter(|x| !x.locked)
+        .map(|x| x.players.clone())
+        .collect();
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // The pairing seed stays, so the teams can still be reproduced with `pair --seed`
+    result.naming_seed = Some(seed);
+
+    let locked_names: Vec<String> = result
+        .teams
+        .iter()
+        .filter(|x| x.locked)
+        .map(|x| x.name.clone())
+        .collect();
+
+    let mut names = name_teams(teams, &locked_names, &adjectives, &nouns, &mut rng, true)
+        .into_iter()
+        .map(|x| x.name);
+
+    for team in result.teams.iter_mut().filter(|x| !x.locked) {
+        if let Some(name) = names.next() {
+            team.name = name;
         }
     }
 
-     let serialized = serde_json::to_string_pretty(&players).unwrap();
-
-     std::fs::write("players_generated.json", serialized.to_string()).unwrap();
-     */
+    let config = result.config.clone();
+    let target_rank = result.target_rank(&config);
+    result.fill_breakdowns(target_rank, &config);
+    result.summary = Some(Summary::of(&result, target_rank, &config));
 
-    let started = std::time::Instant::now();
-
-    let mut sum_of_ranks: f64 = 0.0;
-    for i in 0..players.len() {
-        sum_of_ranks += players.get(i).unwrap().rank as f64;
+    for team in result.teams.iter() {
+        cprintln!("<green>{}</green>", team.name);
     }
-    let average_rank = sum_of_ranks / players.len() as f64;
-
-    cprintln!("<magenta>Average rank: {:.2}</magenta>", average_rank);
 
-    let mut possible_teams = Vec::new();
+    cprintln!("<magenta>Naming seed: {}</magenta>", seed);
 
-    for goalie_index in 0..players.len() {
-        let mut players_without_goalie = players.clone();
-        players_without_goalie.remove(goalie_index);
+    write_json(args.output.as_ref().unwrap_or(&args.teams), &result)
+}
 
-        for midfield_index in 0..players_without_goalie.len() {
-            let mut players_without_goalie_and_midfield = players_without_goalie.clone();
-            players_without_goalie_and_midfield.remove(midfield_index);
+fn generate(args: GenerateArgs) -> Result<(), CliError> {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
 
-            for forward_index in 0..players_without_goalie_and_midfield.len() {
-                let goalie = players[goalie_index].clone();
-                let midfield = players_without_goalie[midfield_index].clone();
-                let forward = players_without_goalie_and_midfield[forward_index].clone();
+    let players = generate_players(args.count, &mut rng);
 
-                let team = Team::new(goalie, midfield, forward);
+    cprintln!(
+        "<green>Generated {} players with seed {}</green>",
+        players.len(),
+        seed
+    );
 
-                let score = team.score(average_rank * 3_f64);
+    write_json(&args.output, &players)
+}
 
-                possible_teams.push((team, score));
-            }
+/// Prints the score and players of each team
+fn print_result(result: &omega_strikers_pairing::Result, target_rank: f64, config: &ScoringConfig) {
+    cprintln!("<magenta>Scoring config: {:?}</magenta>", config);
+    if let Some(seed) = result.seed {
+        cprintln!("<magenta>Seed: {}</magenta>", seed);
+    }
+    if let Some(ref report) = result.solver {
+        cprintln!("<magenta>Solver: {}</magenta>", report.solver);
+        if report.optimal {
+            cprintln!("<green>Proved optimal</green>");
+        } else if let (Some(upper_bound), Some(gap)) = (report.upper_bound, report.gap()) {
+            cprintln!(
+                "<yellow>Not proved optimal, upper bound {:.2} (gap {:.2}%)</yellow>",
+                upper_bound,
+                gap * 100.0
+            );
+        }
+        if let Some(improvement) = report.local_search_improvement {
+            cprintln!(
+                "<magenta>Local search improved the score by {:.2}</magenta>",
+                improvement
+            );
         }
     }
-
-    // Sort by descending score
-    possible_teams.sort_by(|x, y| y.1.total_cmp(&x.1));
-
-    // Make a result by just going from the top -- greedy
-    let mut teams = Vec::new();
-
-    let mut phase = 0;
-
-    while possible_teams.len() > 0 {
-        let mut team_scores_this_phase = Vec::new();
-
+    cprintln!(
+        "<green>Result score: {}</green>",
+        result.score(target_rank, config)
+    );
+    if config.objective != Objective::Sum {
         cprintln!(
-            "<green>Phase {}:</green> <blue>{} teams left</blue>",
-            phase,
-            possible_teams.len()
+            "<green>Objective ({}): {:.2}</green>",
+            config.objective,
+            result.objective_value(target_rank, config)
         );
-
-        let team;
-
-        if possible_teams.len() < 10_000 {
-            for i in 0..possible_teams.len() {
-                // Assess all the teams in this phase, along with the teams left after we've taken it
-                //
-                // E.g. look one step ahead
-                let team = possible_teams.get(i).unwrap().clone();
-
-                // Hypothetically, take this team and see which ones are left
-                let mut possible_teams_next = possible_teams.clone();
-
-                // Hypothetically take this team
-                possible_teams_next = possible_teams_next
-                    .into_iter()
-                    .filter(|other_possible_team| {
-                        !other_possible_team.0.in_team(team.0.goalie.id)
-                            && !other_possible_team.0.in_team(team.0.midfield.id)
-                            && !other_possible_team.0.in_team(team.0.forward.id)
-                    })
-                    .collect();
-
-                // Compute the score of this + leftovers
-                let mut uber_score = team.0.score(average_rank * 3_f64);
-
-                let mut sum_of_scores = 0.0;
-
-                for j in 0..possible_teams_next.len() {
-                    sum_of_scores += possible_teams_next[j].1;
-                }
-
-                uber_score += sum_of_scores / possible_teams_next.len() as f64;
-
-                team_scores_this_phase.push((team, uber_score));
-            }
-
-            // Sort by the next scores, take the best one
-            team_scores_this_phase.sort_by(|x, y| y.1.total_cmp(&x.1));
-
-            team = team_scores_this_phase.get(0).unwrap().0.clone();
-        } else {
-            // Take the best team, for now don't bother
-            team = possible_teams.get(0).unwrap().clone();
-        }
-
-        teams.push(team.0.clone());
-
-        // Take this team, actually this time
-        possible_teams = possible_teams
-            .into_iter()
-            .filter(|other_possible_team| {
-                !other_possible_team.0.in_team(team.0.goalie.id)
-                    && !other_possible_team.0.in_team(team.0.midfield.id)
-                    && !other_possible_team.0.in_team(team.0.forward.id)
-            })
+    }
+    let spread = StrengthSpread::of(&result.team_strengths(config));
+    cprintln!(
+        "<magenta>Team strength: min {:.1}, max {:.1}, stddev {:.2}</magenta>",
+        spread.min,
+        spread.max,
+        spread.stddev
+    );
+    cprintln!("");
+    cprintln!("Teams: ");
+    for team in result.teams.iter() {
+        let locked = if team.locked { " (locked)" } else { "" };
+        let breakdown = team.players.breakdown(target_rank, config);
+        let role_scores: Vec<String> = breakdown
+            .roles
+            .iter()
+            .map(|x| format!("{:+.1}", x.score))
             .collect();
-
-        phase += 1;
+        cprintln!(
+            "<green>{}{} -> {:.1}:</green>",
+            team.name,
+            locked,
+            breakdown.total
+        );
+        cprintln!("	<blue>goalie  : {}, <magenta>rank {}</magenta>, <cyan>role {} ({})</cyan>, <red>hates: {:?}</red></blue>", team.players.goalie.id, rank_of(&team.players.goalie), team.players.goalie.role_preferences, role_scores[0], team.players.goalie.blacklisted_players);
+        cprintln!("	<blue>midfield: {}, <magenta>rank {}</magenta>, <cyan>role {} ({})</cyan>, <red>hates: {:?}</red></blue>", team.players.midfield.id, rank_of(&team.players.midfield), team.players.midfield.role_preferences, role_scores[1], team.players.midfield.blacklisted_players);
+        cprintln!("	<blue>forward : {}, <magenta>rank {}</magenta>, <cyan>role {} ({})</cyan>, <red>hates: {:?}</red></blue>", team.players.forward.id, rank_of(&team.players.forward), team.players.forward.role_preferences, role_scores[2], team.players.forward.blacklisted_players);
+        let rank_deviation = format!("{:+.1}", breakdown.rank_deviation);
+        let rank_score = format!("{:+.1}", breakdown.rank_score);
+        cprintln!(
+            "	<magenta>rank sum {:.1}, {} off the target: {}</magenta>",
+            breakdown.rank_sum,
+            rank_deviation,
+            rank_score
+        );
+        if !breakdown.blacklisted_pairs.is_empty() {
+            let pairs: Vec<String> = breakdown
+                .blacklisted_pairs
+                .iter()
+                .map(|[a, b]| format!("{} & {}", a, b))
+                .collect();
+            cprintln!(
+                "	<red>blacklisted: {}: -{:.1}</red>",
+                pairs.join(", "),
+                breakdown.blacklist_penalty
+            );
+        }
+        if !breakdown.preferred_teammates.is_empty() {
+            let pairs: Vec<String> = breakdown
+                .preferred_teammates
+                .iter()
+                .map(|[player, teammate]| format!("{} -> {}", player, teammate))
+                .collect();
+            cprintln!(
+                "	<cyan>preferred teammates: {}: +{:.1}</cyan>",
+                pairs.join(", "),
+                breakdown.preferred_teammate_bonus
+            );
+        }
+        for rule in breakdown.rules.iter() {
+            let score = format!("{:+.1}", rule.score);
+            cprintln!("	<cyan>{}: {}</cyan>", rule.name, score);
+        }
     }
+    let unsatisfied_requests = result.find_unsatisfied_requests();
+    if !unsatisfied_requests.is_empty() {
+        cprintln!("<yellow>Unsatisfied teammate requests:</yellow>");
+        for request in unsatisfied_requests.iter() {
+            let kind = if request.required {
+                "required"
+            } else {
+                "preferred"
+            };
+            cprintln!(
+                "	<blue>{} -> {} <red>({})</red></blue>",
+                request.player,
+                request.teammate,
+                kind
+            );
+        }
+    }
+    if !result.substitutes.is_empty() {
+        cprintln!("<yellow>Substitutes:</yellow>");
+        for player in result.substitutes.iter() {
+            cprintln!("	<blue>{}, <magenta>rank {}</magenta>, <cyan>role {}</cyan>, <red>hates: {:?}</red></blue>", player.id, rank_of(player), player.role_preferences, player.blacklisted_players);
+        }
+    }
+    print_summary(&Summary::of(result, target_rank, config));
+}
 
-    // Name the teams
-    let mut name_hashset = HashSet::new();
-
-    let mut named_teams = Vec::new();
-
-    let mut random = rand::thread_rng();
-
-    let mut available_team_adjectives = team_name_adjectives_deserialized.clone();
-    let mut available_team_nouns = team_name_nouns_deserialized.clone();
-
-    for team in teams.into_iter() {
-
-		  // Refresh the list if it is too small
-		  if available_team_adjectives.len() <= 2 && available_team_nouns.len() <= 2 {
-			 available_team_nouns = team_name_nouns_deserialized.clone();
-			 available_team_adjectives = team_name_adjectives_deserialized.clone();
-		  }
-
-        let adjective_i = random.gen_range(0..available_team_adjectives.len());
-        let adjective = available_team_adjectives[adjective_i].clone();
-
-        let noun_i = random.gen_range(0..available_team_nouns.len());
-        let noun = available_team_nouns[noun_i].clone();
-
-        let mut team_name = format!("{adjective} {noun}");
-
-        let mut iterations: usize = 0;
-        let mut team_name_unique = !name_hashset.contains(&team_name);
-
-        while !team_name_unique {
-            let adjective_i = random.gen_range(0..available_team_adjectives.len());
-            let adjective = available_team_adjectives[adjective_i].clone();
-
-            let noun_i = random.gen_range(0..available_team_nouns.len());
-            let noun = available_team_nouns[noun_i].clone();
-
-            team_name = format!("{adjective} {noun}");
-
-				team_name_unique = name_hashset.contains(&team_name);
+/// Prints how satisfied the players are over the whole tournament
+fn print_summary(summary: &Summary) {
+    cprintln!("");
+    cprintln!("Summary: ");
+    cprintln!(
+        "	<cyan>roles: {} first choice, {} second choice, {} third choice, {} unwanted</cyan>",
+        summary.first_choice,
+        summary.second_choice,
+        summary.third_choice,
+        summary.unwanted_role
+    );
+    if summary.blacklist_violations > 0 {
+        cprintln!(
+            "	<red>{} blacklisted pairs on the same team</red>",
+            summary.blacklist_violations
+        );
+    } else {
+        cprintln!("	<green>no blacklisted pairs on the same team</green>");
+    }
+    if summary.substitutes > 0 {
+        cprintln!("	<yellow>{} substitutes</yellow>", summary.substitutes);
+    }
+    cprintln!(
+        "	<magenta>rank sums (target {:.1}):</magenta>",
+        summary.target_rank
+    );
+    for bucket in summary.rank_sum_distribution.iter() {
+        let bar = "#".repeat(bucket.teams);
+        cprintln!(
+            "		<magenta>{:.1} to {:.1}: {} ({})</magenta>",
+            bucket.from,
+            bucket.to,
+            bar,
+            bucket.teams
+        );
+    }
+    cprintln!("	<yellow>worst off:</yellow>");
+    for player in summary.worst_off.iter() {
+        let choice = match player.choice {
+            Some(choice) => format!("choice {}", choice),
+            None => "unwanted".to_string(),
+        };
+        let mut problems = Vec::new();
+        if !player.blacklisted_teammates.is_empty() {
+            problems.push(format!(
+                "with blacklisted {}",
+                player.blacklisted_teammates.join(", ")
+            ));
+        }
+        if !player.missed_teammates.is_empty() {
+            problems.push(format!("without {}", player.missed_teammates.join(", ")));
+        }
+        let problems = if problems.is_empty() {
+            String::new()
+        } else {
+            format!(", {}", problems.join(", "))
+        };
+        cprintln!(
+            "		<blue>{} on {}: {:?} ({}){} -> {:.1}</blue>",
+            player.player,
+            player.team,
+            player.role,
+            choice,
+            problems,
+            player.score
+        );
+    }
+}
 
-            iterations += 1;
+/// Describes a player's rank, along with their rating if they have one
+fn rank_of(player: &StringPlayer) -> String {
+    match player.rating {
+        Some(rating) => format!("{} ({})", player.rank, rating),
+        None => player.rank.to_string(),
+    }
+}
 
-            if iterations > 100_000 {
-                println!("Failed to find a unique name after 100.000 iterations, you likely do not have enough random words");
-                println!(
-                    "You will have to manually find a different name for '{}'",
-                    team_name
-                );
-                break;
-            }
-        }
+/// Reads and deserializes a json file
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, CliError> {
+    let read_to_string =
+        std::fs::read_to_string(path).map_err(|e| CliError::Read(path.to_path_buf(), e))?;
 
-        name_hashset.insert(team_name.clone());
+    serde_json::from_str(&read_to_string)
+        .map_err(|e| CliError::Deserialize(path.to_path_buf(), e.to_string()))
+}
 
-		  available_team_adjectives.remove(adjective_i);
-		  available_team_nouns.remove(noun_i);
+/// Reads the players, along with the locked teams if the file has a `locked` section
+fn read_players(path: &Path) -> Result<(Vec<StringPlayer>, Vec<LockedTeam>), CliError> {
+    let read_to_string =
+        std::fs::read_to_string(path).map_err(|e| CliError::Read(path.to_path_buf(), e))?;
 
-        let string_team = team.to_string_team(&player_names);
+    let input = PlayersInput::from_json_str(&read_to_string)
+        .map_err(|e| CliError::Deserialize(path.to_path_buf(), e.to_string()))?;
 
-        let named_team = NamedTeam {
-            players: string_team,
-            name: team_name,
-        };
+    Ok((input.players, input.locked))
+}
 
-        named_teams.push(named_team);
-    }
+/// Reads a scoring config, as json if the file ends with .json and as toml otherwise
+fn read_config(path: &Path) -> Result<ScoringConfig, CliError> {
+    let read_to_string =
+        std::fs::read_to_string(path).map_err(|e| CliError::Read(path.to_path_buf(), e))?;
 
-    let result = Result { teams: named_teams };
+    let res = if path.extension().is_some_and(|x| x == "json") {
+        ScoringConfig::from_json_str(&read_to_string).map_err(|e| e.to_string())
+    } else {
+        ScoringConfig::from_toml_str(&read_to_string).map_err(|e| e.to_string())
+    };
 
-    cprintln!("<cyan>Took {:?}</cyan>", started.elapsed());
-    cprintln!(
-        "<green>Result score: {}</green>",
-        result.score(average_rank * 3_f64)
-    );
-    cprintln!("");
-    cprintln!("Teams: ");
-    for i in 0..result.teams.len() {
-        let team = &result.teams[i];
-        cprintln!(
-            "<green>{} -> {:.1}:</green>",
-            team.name,
-            team.players.score(average_rank * 3.0_f64)
-        );
-        cprintln!("	<blue>goalie  : {}, <magenta>rank {}</magenta>, <cyan>role {:?}</cyan>, <red>hates: {:?}</red></blue>", team.players.goalie.id, team.players.goalie.rank, team.players.goalie.role_preferences, team.players.goalie.blacklisted_players);
-        cprintln!("	<blue>midfield: {}, <magenta>rank {}</magenta>, <cyan>role {:?}</cyan>, <red>hates: {:?}</red></blue>", team.players.midfield.id, team.players.midfield.rank, team.players.midfield.role_preferences, team.players.midfield.blacklisted_players);
-        cprintln!("	<blue>forward : {}, <magenta>rank {}</magenta>, <cyan>role {:?}</cyan>, <red>hates: {:?}</red></blue>", team.players.forward.id, team.players.forward.rank, team.players.forward.role_preferences, team.players.forward.blacklisted_players);
-    }
+    res.map_err(|e| CliError::Deserialize(path.to_path_buf(), e))
+}
 
-    let serialized = serde_json::to_string_pretty(&result).unwrap();
+/// Reads the adjective and noun lists
+fn read_words(args: &WordArgs) -> Result<(Vec<String>, Vec<String>), CliError> {
+    let adjectives = read_json(&args.adjectives)?;
+    let nouns = read_json(&args.nouns)?;
 
-    let res = std::fs::write("output_teams.json", serialized.as_bytes());
+    Ok((adjectives, nouns))
+}
 
-    if let Err(e) = res {
-        println!("Failed to write output: {}", e);
-    }
+/// Serializes and writes a json file
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), CliError> {
+    let serialized = serde_json::to_string_pretty(value).unwrap();
 
-    pause();
+    std::fs::write(path, serialized.as_bytes()).map_err(|e| CliError::Write(path.to_path_buf(), e))
 }
 
 fn pause() {
@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+
+use crate::types::{NamedTeam, StringTeam};
+
+/// How many times we try to find a unique name before giving up
+pub const MAX_NAME_ITERATIONS: usize = 100_000;
+
+/// Names the teams with random, unique "adjective noun" names.
+///
+/// Used words are removed from the lists until they run out, after which the lists are refreshed.
+///
+/// If either list is empty, teams are simply numbered.
+///
+/// No team gets one of the `taken` names, e.g. the names of locked teams.
+pub fn name_teams<R: Rng>(
+    teams: Vec<StringTeam>,
+    taken: &[String],
+    adjectives: &[String],
+    nouns: &[String],
+    random: &mut R,
+    verbose: bool,
+) -> Vec<NamedTeam> {
+    let mut name_hashset: HashSet<String> = taken.iter().cloned().collect();
+
+    let mut named_teams = Vec::new();
+
+    let mut available_team_adjectives = adjectives.to_vec();
+    let mut available_team_nouns = nouns.to_vec();
+
+    let mut number = 0;
+
+    for string_team in teams.into_iter() {
+        if adjectives.is_empty() || nouns.is_empty() {
+            number += 1;
+
+            while name_hashset.contains(&format!("Team {}", number)) {
+                number += 1;
+            }
+
+            named_teams.push(NamedTeam {
+                players: string_team,
+                name: format!("Team {}", number),
+                locked: false,
+                breakdown: None,
+            });
+            continue;
+        }
+
+        // Refresh the list if it is too small
+        if available_team_adjectives.is_empty() || available_team_nouns.is_empty() {
+            available_team_nouns = nouns.to_vec();
+            available_team_adjectives = adjectives.to_vec();
+        }
+
+        let mut adjective_i = random.gen_range(0..available_team_adjectives.len());
+        let mut noun_i = random.gen_range(0..available_team_nouns.len());
+
+        let mut team_name = format!(
+            "{} {}",
+            available_team_adjectives[adjective_i], available_team_nouns[noun_i]
+        );
+
+        let mut iterations: usize = 0;
+
+        while name_hashset.contains(&team_name) {
+            adjective_i = random.gen_range(0..available_team_adjectives.len());
+            noun_i = random.gen_range(0..available_team_nouns.len());
+
+            team_name = format!(
+                "{} {}",
+                available_team_adjectives[adjective_i], available_team_nouns[noun_i]
+            );
+
+            iterations += 1;
+
+            if iterations > MAX_NAME_ITERATIONS {
+                if verbose {
+                    println!("Failed to find a unique name after 100.000 iterations, you likely do not have enough random words");
+                    println!(
+                        "You will have to manually find a different name for '{}'",
+                        team_name
+                    );
+                }
+                break;
+            }
+        }
+
+        name_hashset.insert(team_name.clone());
+
+        available_team_adjectives.remove(adjective_i);
+        available_team_nouns.remove(noun_i);
+
+        named_teams.push(NamedTeam {
+            players: string_team,
+            name: team_name,
+            locked: false,
+            breakdown: None,
+        });
+    }
+
+    named_teams
+}
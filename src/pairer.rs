@@ -0,0 +1,1082 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use color_print::cprintln;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::config::{BlacklistMode, Objective, ScoringConfig};
+use crate::naming::name_teams;
+use crate::scoring::{PlayerLike, TeamLike};
+use crate::solver::{
+    annealing::{solve_annealing, AnnealingOptions},
+    best_role_assignment,
+    exact::solve_exact,
+    greedy::{fit_in_leftovers, solve_greedy},
+    local_search::{improve_substitutes, improve_teams},
+    Solver,
+};
+use crate::summary::Summary;
+use crate::types::{
+    IndexTeam, LockedTeam, Player, Result, StrengthSpread, StringPlayer, StringTeam,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+/// How we pick substitutes when the number of players isn't a multiple of three
+pub enum SubstitutePolicy {
+    /// Whoever the teams would miss the least
+    #[default]
+    LowestImpact,
+    /// Players who set `substitute_ok`, the ones the teams would miss the least if there are
+    /// more than needed. If there aren't enough, the rest is picked by lowest impact.
+    /// Volunteers with required teammates keep playing, and players who can't be on any team
+    /// with hard blacklists sit out first
+    Volunteers,
+}
+
+impl FromStr for SubstitutePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "lowestimpact" => Ok(SubstitutePolicy::LowestImpact),
+            "volunteers" => Ok(SubstitutePolicy::Volunteers),
+            _ => Err(format!(
+                "unknown substitute policy '{}', expected lowest-impact or volunteers",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for SubstitutePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubstitutePolicy::LowestImpact => write!(f, "lowest-impact"),
+            SubstitutePolicy::Volunteers => write!(f, "volunteers"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// Why players couldn't be paired
+pub enum PairingError {
+    /// Blacklists are hard, but these players hate or are hated by so many others that there
+    /// is no team they could be on, and there are more of them than there are substitutes
+    NoAllowedTeam(Vec<String>),
+    /// The solver couldn't fit these players into teams without splitting up required
+    /// teammates, putting someone in a role they never play or breaking a hard blacklist.
+    /// Another solver may still manage, the message suggests one
+    SolverFailed(Solver, Vec<String>),
+    /// These players are in more than one locked team, ignoring case
+    LockedTeamsOverlap(Vec<String>),
+    /// These players all have to play together, directly or through eachother, but there are
+    /// more than three of them
+    OversizedGroup(Vec<String>),
+}
+
+impl Display for PairingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PairingError::NoAllowedTeam(players) => write!(
+                f,
+                "Blacklists can't be kept: {} can't be put on any team without someone who hates them or whom they hate",
+                players.join(", ")
+            ),
+            PairingError::SolverFailed(solver, players) => {
+                // Annealing tends to manage where the others don't, except when it is what failed
+                let other = match solver {
+                    Solver::Annealing => Solver::Greedy,
+                    _ => Solver::Annealing,
+                };
+
+                write!(
+                    f,
+                    "The {} solver failed to put {} on a team without splitting up required teammates, putting someone in a role they never play or breaking a hard blacklist. Another solver may still manage, e.g. --solver {}",
+                    solver,
+                    players.join(", "),
+                    other
+                )
+            }
+            PairingError::OversizedGroup(players) => write!(
+                f,
+                "{} have to play together, but a team only has 3 players",
+                players.join(", ")
+            ),
+            PairingError::LockedTeamsOverlap(players) => write!(
+                f,
+                "{} are in more than one locked team, but can only play on one",
+                players.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PairingError {}
+
+/// How long solvers which support it may search for by default
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, PartialEq, Debug)]
+/// Options for a [`Pairer`]
+pub struct PairingOptions {
+    /// Weights used when scoring teams
+    pub scoring: ScoringConfig,
+    /// Which algorithm to pick teams with
+    pub solver: Solver,
+    /// How long solvers which support it may search for, unlimited if None. Defaults to 30
+    /// seconds, as the exact solver can take very long to prove its teams are the best
+    pub time_limit: Option<Duration>,
+    /// Options for the annealing solver
+    pub annealing: AnnealingOptions,
+    /// Seed for all randomness, so the same seed gives the same teams. Random if None
+    pub seed: Option<u64>,
+    /// Whether to improve the picked teams by swapping players between them
+    pub local_search: bool,
+    /// How we pick substitutes when the number of players isn't a multiple of three
+    pub substitute_policy: SubstitutePolicy,
+    /// Below how many candidate teams we start looking one step ahead when picking a team
+    pub lookahead_threshold: usize,
+    /// How many of the best role assignments of each trio of players the greedy solver considers
+    pub assignments_per_trio: usize,
+    /// How many possible teams the greedy solver keeps in memory at once
+    pub max_candidates: usize,
+    /// Adjectives to generate team names from
+    pub adjectives: Vec<String>,
+    /// Nouns to generate team names from
+    pub nouns: Vec<String>,
+    /// How many threads to score teams on, all cores if None. Only used with the `parallel` feature,
+    /// ignored with a warning otherwise
+    pub threads: Option<usize>,
+    /// Whether to print progress and warnings to stdout
+    pub verbose: bool,
+}
+
+impl Default for PairingOptions {
+    fn default() -> Self {
+        Self {
+            scoring: ScoringConfig::default(),
+            solver: Solver::default(),
+            time_limit: Some(DEFAULT_TIME_LIMIT),
+            annealing: AnnealingOptions::default(),
+            seed: None,
+            local_search: true,
+            substitute_policy: SubstitutePolicy::default(),
+            lookahead_threshold: 10_000,
+            assignments_per_trio: 1,
+            max_candidates: 1_000_000,
+            adjectives: Vec::new(),
+            nouns: Vec::new(),
+            threads: None,
+            verbose: false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+/// The pairing engine.
+///
+/// Takes a list of players and splits them into teams of three.
+pub struct Pairer {
+    pub players: Vec<StringPlayer>,
+    /// Teams formed before pairing, their players are left out of pairing even if they are
+    /// also in `players`
+    pub locked_teams: Vec<LockedTeam>,
+    pub options: PairingOptions,
+}
+
+impl Pairer {
+    pub fn new(players: Vec<StringPlayer>, options: PairingOptions) -> Self {
+        Self {
+            players,
+            locked_teams: Vec::new(),
+            options,
+        }
+    }
+
+    /// Sets the teams formed before pairing
+    pub fn with_locked_teams(mut self, locked_teams: Vec<LockedTeam>) -> Self {
+        self.locked_teams = locked_teams;
+        self
+    }
+
+    /// Returns every player once, the ones in locked teams last, as goalie, midfield, forward.
+    ///
+    /// Ids are matched ignoring case, like everywhere else.
+    pub fn all_players(&self) -> Vec<StringPlayer> {
+        let locked: Vec<&StringPlayer> = self
+            .locked_teams
+            .iter()
+            .flat_map(|x| x.players.players())
+            .collect();
+
+        self.players
+            .iter()
+            .filter(|x| !locked.iter().any(|locked| locked.is(&x.id)))
+            .chain(locked.iter().copied())
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the average rank of all players, including the ones in locked teams
+    pub fn average_rank(&self) -> f64 {
+        let players = self.all_players();
+
+        if players.is_empty() {
+            return 0.0;
+        }
+
+        let sum_of_ranks: f64 = players.iter().map(|x| x.rank).sum();
+
+        sum_of_ranks / players.len() as f64
+    }
+
+    /// Returns the rank sum we would like each team to have
+    ///
+    /// It is in the unit of `options.scoring.rating.unit`, so with ratings it's a rating sum.
+    pub fn target_rank(&self) -> f64 {
+        let players = self.all_players();
+
+        if players.is_empty() {
+            return 0.0;
+        }
+
+        let rating = &self.options.scoring.rating;
+        let sum_of_strengths: f64 = players.iter().map(|x| x.strength(rating)).sum();
+
+        sum_of_strengths / players.len() as f64 * 3_f64
+    }
+
+    /// Pairs the players into named teams
+    ///
+    /// Fails if blacklists are hard and there's no way to keep them, if more than three players
+    /// have to play together, if players who aren't needed as substitutes couldn't be put on a
+    /// team, or if a player is in more than one locked team.
+    pub fn pair(&self) -> std::result::Result<Result, PairingError> {
+        let overlap = locked_team_overlap(&self.locked_teams);
+
+        if !overlap.is_empty() {
+            return Err(PairingError::LockedTeamsOverlap(overlap));
+        }
+
+        #[cfg(feature = "parallel")]
+        if let Some(threads) = self.options.threads {
+            // Not getting our own threads is no reason to fail, the global pool works just as well
+            match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+                Ok(pool) => return pool.install(|| self.pair_teams()),
+                Err(e) => {
+                    if self.options.verbose {
+                        cprintln!(
+                            "<yellow>Failed to build a pool of {} threads ({}), using all cores instead</yellow>",
+                            threads,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        if let Some(threads) = self.options.threads.filter(|_| self.options.verbose) {
+            cprintln!(
+                "<yellow>Ignoring {} threads, scoring runs on one thread without the parallel feature</yellow>",
+                threads
+            );
+        }
+
+        self.pair_teams()
+    }
+
+    fn pair_teams(&self) -> std::result::Result<Result, PairingError> {
+        let all_players = self.all_players();
+
+        let (player_names, mut players) = intern_players(&all_players, self.options.verbose);
+
+        // Players in locked teams come last, everyone before them is free to be paired
+        let free_count = all_players.len() - self.locked_teams.len() * 3;
+
+        // Nobody can join a locked team, so having to play with its players can't work out.
+        // Only the solvers work with these players, the result is built from `all_players`, so
+        // the request is still reported as unsatisfied afterwards
+        for player in players[..free_count].iter_mut() {
+            player.required_teammates.retain(|x| *x < free_count);
+            player.required_by.retain(|x| *x < free_count);
+        }
+
+        // No solver can keep such a group together, so don't make them try
+        if let Some(group) = oversized_groups(&players[..free_count]).first() {
+            return Err(PairingError::OversizedGroup(
+                group
+                    .iter()
+                    .map(|x| player_names[players[*x].id].clone())
+                    .collect(),
+            ));
+        }
+
+        let target_rank = self.target_rank();
+
+        if self.options.verbose {
+            cprintln!(
+                "<magenta>Average rank: {:.2}</magenta>",
+                self.average_rank()
+            );
+        }
+
+        // Written into the result, so it has to say exactly what the teams were scored with
+        let config = &self.options.scoring.resolved();
+
+        let seed = self.options.seed.unwrap_or_else(rand::random);
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut substitutes: Vec<usize> = Vec::new();
+
+        // The players who are left for the solver, the solver refers to them by index in here
+        let mut pool: Vec<usize> = (0..free_count).collect();
+
+        if config.blacklist_mode == BlacklistMode::Hard {
+            let pool_players: Vec<Player> = pool.iter().map(|x| players[*x].clone()).collect();
+            let without_team: Vec<usize> = players_without_allowed_team(&pool_players)
+                .iter()
+                .map(|x| pool[*x])
+                .collect();
+
+            // Players who can't be on any team can still sit out, as long as we need that many
+            // substitutes anyway
+            if without_team.len() > pool.len() % 3 {
+                return Err(PairingError::NoAllowedTeam(
+                    without_team
+                        .iter()
+                        .map(|x| player_names[players[*x].id].clone())
+                        .collect(),
+                ));
+            }
+
+            pool.retain(|x| !without_team.contains(x));
+            substitutes.extend(without_team);
+        }
+
+        // Volunteers take whichever bench spots are left before we even start. Not the ones who
+        // have to play with someone, as they can't sit out without them
+        if self.options.substitute_policy == SubstitutePolicy::Volunteers {
+            let volunteers: Vec<usize> = pool
+                .iter()
+                .copied()
+                .filter(|x| {
+                    let player = &players[*x];
+
+                    player.substitute_ok
+                        && player.required_teammates.is_empty()
+                        && player.required_by.is_empty()
+                })
+                .take(pool.len() % 3)
+                .collect();
+
+            pool.retain(|x| !volunteers.contains(x));
+            substitutes.extend(volunteers);
+        }
+
+        let pool_players: Vec<Player> = pool.iter().map(|x| players[*x].clone()).collect();
+
+        if self.options.verbose && config.deviation_below_a_rank() {
+            cprintln!(
+                "<yellow>allowed_rank_deviation is {}, less than one rank's worth of rating ({}), so hardly any team gets a rank score</yellow>",
+                config.allowed_deviation(),
+                config.rating.in_unit(1.0)
+            );
+        }
+
+        // Only the local search and the annealing solver look at the objective
+        if self.options.verbose
+            && config.objective != Objective::Sum
+            && self.options.solver != Solver::Annealing
+            && !self.options.local_search
+        {
+            cprintln!(
+                "<yellow>The {} solver picks teams by their summed score, without the local search the {} objective only changes what is reported</yellow>",
+                self.options.solver,
+                config.objective
+            );
+        }
+
+        let (teams, mut report) = match self.options.solver {
+            Solver::Greedy => solve_greedy(
+                &pool_players,
+                target_rank,
+                config,
+                self.options.lookahead_threshold,
+                self.options.assignments_per_trio,
+                self.options.max_candidates,
+                self.options.verbose,
+            ),
+            Solver::Exact => solve_exact(
+                &pool_players,
+                target_rank,
+                config,
+                self.options.time_limit,
+                self.options.verbose,
+            ),
+            Solver::Annealing => solve_annealing(
+                &pool_players,
+                target_rank,
+                config,
+                &self.options.annealing,
+                &mut rng,
+                self.options.verbose,
+            ),
+        };
+
+        // The exact solver proves things about the summed score, which says nothing about how
+        // far off the best teams we are for other objectives
+        if config.objective != Objective::Sum {
+            report.optimal = false;
+            report.upper_bound = None;
+        }
+
+        let mut teams: Vec<IndexTeam> = teams
+            .iter()
+            .map(|x| IndexTeam::new(pool[x.goalie], pool[x.midfield], pool[x.forward]))
+            .collect();
+
+        // Only the annealing solver may still pick such teams. Try fitting their players into
+        // other teams instead, by splitting those up
+        if teams.iter().any(|x| !x.is_allowed(&players, config)) {
+            teams.retain(|x| x.is_allowed(&players, config));
+
+            let mut taken: Vec<bool> = (0..players.len()).map(|x| !pool.contains(&x)).collect();
+
+            for member in teams.iter().flat_map(|x| x.members()) {
+                taken[member] = true;
+            }
+
+            fit_in_leftovers(&mut teams, &mut taken, &players, target_rank, config);
+
+            report.score = teams
+                .iter()
+                .map(|x| x.score(&players, target_rank, config))
+                .sum();
+        }
+
+        // Substitutes only make up for the players who don't fit in teams of three
+        let left_out: Vec<String> = pool
+            .iter()
+            .filter(|x| !teams.iter().any(|team| team.in_team(**x)))
+            .map(|x| player_names[players[*x].id].clone())
+            .collect();
+
+        if left_out.len() > pool.len() % 3 {
+            return Err(PairingError::SolverFailed(self.options.solver, left_out));
+        }
+
+        // Whoever the solver left out is a substitute too
+        for player in pool {
+            if !teams.iter().any(|x| x.in_team(player)) {
+                substitutes.push(player);
+            }
+        }
+
+        if self.options.local_search {
+            let improvement = improve_teams(
+                &mut teams,
+                &players,
+                target_rank,
+                config,
+                self.options.verbose,
+            );
+
+            report.score += improvement;
+            report.local_search_improvement = Some(improvement);
+        }
+
+        // Sit out whoever the teams miss the least, only swapping in volunteers if we should
+        let policy = self.options.substitute_policy;
+
+        report.score += improve_substitutes(
+            &mut teams,
+            &mut substitutes,
+            &players,
+            target_rank,
+            config,
+            |substitute, player| match policy {
+                SubstitutePolicy::LowestImpact => true,
+                SubstitutePolicy::Volunteers => {
+                    players[player].substitute_ok || !players[substitute].substitute_ok
+                }
+            },
+        );
+
+        substitutes.sort();
+
+        let substitutes: Vec<StringPlayer> = substitutes
+            .iter()
+            .map(|x| all_players[*x].clone())
+            .collect();
+
+        if self.options.verbose && !substitutes.is_empty() {
+            let substitute_ids: Vec<&str> = substitutes.iter().map(|x| x.id.as_str()).collect();
+
+            cprintln!(
+                "<yellow>{} players didn't get a team and are substitutes: {}</yellow>",
+                substitutes.len(),
+                substitute_ids.join(", ")
+            );
+        }
+
+        // Locked teams go first, as is, only putting their players in their best roles if we may
+        let locked_teams: Vec<StringTeam> = self
+            .locked_teams
+            .iter()
+            .enumerate()
+            .map(|(i, locked_team)| {
+                let first = free_count + i * 3;
+
+                let team = if locked_team.fixed_roles {
+                    IndexTeam::new(first, first + 1, first + 2)
+                } else {
+                    let members = [first, first + 1, first + 2];
+
+                    best_role_assignment(members, &players, target_rank, config).0
+                };
+
+                StringTeam::new(
+                    all_players[team.goalie].clone(),
+                    all_players[team.midfield].clone(),
+                    all_players[team.forward].clone(),
+                )
+            })
+            .collect();
+
+        if self.options.verbose && !locked_teams.is_empty() {
+            let locked_score: f64 = locked_teams
+                .iter()
+                .map(|x| x.score(target_rank, config))
+                .sum();
+
+            cprintln!(
+                "<magenta>{} locked teams, scoring {:.2}</magenta>",
+                locked_teams.len(),
+                locked_score
+            );
+        }
+
+        let teams = locked_teams
+            .into_iter()
+            .chain(teams.iter().map(|x| {
+                StringTeam::new(
+                    all_players[x.goalie].clone(),
+                    all_players[x.midfield].clone(),
+                    all_players[x.forward].clone(),
+                )
+            }))
+            .collect();
+
+        // Generated names can't be the same as the name of a locked team
+        let locked_names: Vec<String> = self
+            .locked_teams
+            .iter()
+            .filter_map(|x| x.name.clone())
+            .collect();
+
+        let mut teams = name_teams(
+            teams,
+            &locked_names,
+            &self.options.adjectives,
+            &self.options.nouns,
+            &mut rng,
+            self.options.verbose,
+        );
+
+        for (team, locked_team) in teams.iter_mut().zip(self.locked_teams.iter()) {
+            team.locked = true;
+
+            if let Some(ref name) = locked_team.name {
+                team.name = name.clone();
+            }
+        }
+
+        let mut result = Result {
+            teams,
+            substitutes,
+            unsatisfied_requests: Vec::new(),
+            config: config.clone(),
+            solver: Some(report),
+            seed: Some(seed),
+            naming_seed: None,
+            strength_spread: None,
+            summary: None,
+        };
+
+        result.unsatisfied_requests = result.find_unsatisfied_requests();
+        result.strength_spread = Some(StrengthSpread::of(&result.team_strengths(config)));
+        result.fill_breakdowns(target_rank, config);
+        result.summary = Some(Summary::of(&result, target_rank, config));
+
+        if self.options.verbose {
+            for request in result.unsatisfied_requests.iter() {
+                let kind = if request.required {
+                    "has to"
+                } else {
+                    "would like to"
+                };
+
+                cprintln!(
+                    "<yellow>{} {} play with {}, but they aren't on the same team</yellow>",
+                    request.player,
+                    kind,
+                    request.teammate
+                );
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Returns the ids of players who are in more than one of the locked teams, or twice in one,
+/// ignoring case
+pub(crate) fn locked_team_overlap(locked_teams: &[LockedTeam]) -> Vec<String> {
+    let locked: Vec<&StringPlayer> = locked_teams
+        .iter()
+        .flat_map(|x| x.players.players())
+        .collect();
+
+    let mut overlap: Vec<String> = Vec::new();
+
+    for (i, player) in locked.iter().enumerate() {
+        if locked[..i].iter().any(|x| x.is(&player.id)) && !overlap.iter().any(|x| player.is(x)) {
+            overlap.push(player.id.clone());
+        }
+    }
+
+    overlap
+}
+
+/// Returns the groups of more than three players who have to play together, directly or
+/// through eachother, as indexes into `players`
+pub(crate) fn oversized_groups(players: &[Player]) -> Vec<Vec<usize>> {
+    let mut group: Vec<usize> = (0..players.len()).collect();
+
+    for (i, player) in players.iter().enumerate() {
+        for required in player.required_teammates.iter() {
+            let Some(j) = players.iter().position(|x| x.id == *required) else {
+                continue;
+            };
+
+            let (from, to) = (group[j], group[i]);
+
+            for x in group.iter_mut() {
+                if *x == from {
+                    *x = to;
+                }
+            }
+        }
+    }
+
+    (0..players.len())
+        .map(|i| {
+            (0..players.len())
+                .filter(|x| group[*x] == i)
+                .collect::<Vec<usize>>()
+        })
+        .filter(|x| x.len() > 3)
+        .collect()
+}
+
+/// Returns the indexes of the players who can't be on any team without someone who hates
+/// them or whom they hate
+pub(crate) fn players_without_allowed_team(players: &[Player]) -> Vec<usize> {
+    (0..players.len())
+        .filter(|a| {
+            !(0..players.len()).any(|b| {
+                (b + 1..players.len()).any(|c| {
+                    *a != b
+                        && *a != c
+                        && [&players[*a], &players[b], &players[c]]
+                            .blacklisted_pairs()
+                            .count()
+                            == 0
+                })
+            })
+        })
+        .collect()
+}
+
+/// Converts string players into the more efficient id representation.
+///
+/// Cloning names is expensive, so only keep one instance of them while refering by
+/// index in other cases
+///
+/// Returns the player names (indexed by player id) and the players
+pub fn intern_players(
+    string_players: &[StringPlayer],
+    verbose: bool,
+) -> (Vec<String>, Vec<Player>) {
+    let player_names: Vec<String> = string_players.iter().map(|x| x.id.clone()).collect();
+
+    let mut players = Vec::new();
+
+    for player in string_players {
+        let player_name_index = player_names
+            .iter()
+            .rposition(|name| player.id.eq(name))
+            .unwrap_or_default();
+
+        let blacklisted_players = intern_names(
+            &player_names,
+            &player.blacklisted_players,
+            |name| format!("Player {} hates {}", player.id, name),
+            verbose,
+        );
+
+        let preferred_teammates = intern_names(
+            &player_names,
+            &player.preferred_teammates,
+            |name| format!("Player {} would like to play with {}", player.id, name),
+            verbose,
+        );
+
+        let required_teammates = intern_names(
+            &player_names,
+            &player.required_teammates,
+            |name| format!("Player {} has to play with {}", player.id, name),
+            verbose,
+        );
+
+        if verbose && required_teammates.len() > 2 {
+            println!(
+                "Player {} has to play with {} players, but a team only has room for 2",
+                player.id,
+                required_teammates.len()
+            );
+        }
+
+        let id_player = Player {
+            id: player_name_index,
+            blacklisted_players,
+            preferred_teammates,
+            required_teammates,
+            required_by: Vec::new(),
+            role_preferences: player.role_preferences,
+            rank: player.rank,
+            rating: player.rating,
+            substitute_ok: player.substitute_ok,
+            banned_roles: player.banned_roles.clone(),
+            locked_role: player.locked_role,
+            attributes: player.attributes.clone(),
+        };
+
+        players.push(id_player);
+    }
+
+    // If someone has to play with a player, that player also has to play with them
+    for i in 0..players.len() {
+        for required in players[i].required_teammates.clone() {
+            let id = players[i].id;
+
+            if let Some(required_player) = players.iter_mut().find(|x| x.id == required) {
+                required_player.required_by.push(id);
+            }
+        }
+    }
+
+    (player_names, players)
+}
+
+/// Looks up the ids of players by name, ignoring case.
+///
+/// Names which aren't registered players are ignored, `describe` says who referred to them
+/// in the warning.
+fn intern_names<F: Fn(&str) -> String>(
+    player_names: &[String],
+    names: &[String],
+    describe: F,
+    verbose: bool,
+) -> Vec<usize> {
+    let mut ids = Vec::new();
+
+    for name in names.iter() {
+        let id = player_names
+            .iter()
+            .rposition(|x| name.to_lowercase().eq(&x.to_lowercase()));
+
+        // We didn't find them player name
+        let Some(id) = id else {
+            if verbose {
+                println!(
+                    "{}, but the latter is not a registered player. Ignoring",
+                    describe(name)
+                );
+            }
+            continue;
+        };
+
+        ids.push(id);
+    }
+
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::generate::generate_players;
+    use crate::types::Role;
+
+    #[test]
+    fn greedy_pairs_players_locked_to_the_same_role() {
+        // A third only plays goalie and a third never does, so every team needs exactly one of
+        // the goalies. The greedy picks alone use up the other goalies first
+        let mut players = generate_players(36, &mut StdRng::seed_from_u64(0));
+
+        for (i, player) in players.iter_mut().enumerate() {
+            player.blacklisted_players.clear();
+
+            match i % 3 {
+                0 => player.locked_role = Some(Role::Goalie),
+                1 => player.banned_roles = vec![Role::Goalie],
+                _ => {}
+            }
+        }
+
+        let options = PairingOptions {
+            seed: Some(0),
+            ..Default::default()
+        };
+
+        let result = Pairer::new(players, options).pair().unwrap();
+
+        assert_eq!(result.teams.len(), 12);
+        assert!(result.substitutes.is_empty());
+    }
+
+    /// Seven players without blacklists, the last of whom volunteers to sit out
+    fn seven_players_with_a_volunteer() -> Vec<StringPlayer> {
+        let mut players = generate_players(7, &mut StdRng::seed_from_u64(0));
+
+        for (i, player) in players.iter_mut().enumerate() {
+            player.id = format!("p{}", i);
+            player.blacklisted_players.clear();
+        }
+
+        players[6].substitute_ok = true;
+
+        players
+    }
+
+    #[test]
+    fn volunteers_with_required_teammates_keep_playing() {
+        let mut players = seven_players_with_a_volunteer();
+
+        players[6].substitute_ok = false;
+        players[1].substitute_ok = true;
+        players[0].required_teammates = vec!["p1".to_string()];
+
+        let options = PairingOptions {
+            seed: Some(0),
+            substitute_policy: SubstitutePolicy::Volunteers,
+            ..Default::default()
+        };
+
+        let result = Pairer::new(players, options).pair().unwrap();
+
+        assert_eq!(result.teams.len(), 2);
+        assert!(result.unsatisfied_requests.is_empty());
+        assert!(!result.substitutes.iter().any(|x| x.id == "p1"));
+    }
+
+    #[test]
+    fn players_without_allowed_team_take_the_place_of_volunteers() {
+        let mut players = seven_players_with_a_volunteer();
+
+        players[0].blacklisted_players = (1..7).map(|x| format!("p{}", x)).collect();
+
+        let options = PairingOptions {
+            scoring: ScoringConfig {
+                blacklist_mode: BlacklistMode::Hard,
+                ..Default::default()
+            },
+            seed: Some(0),
+            substitute_policy: SubstitutePolicy::Volunteers,
+            ..Default::default()
+        };
+
+        let result = Pairer::new(players, options).pair().unwrap();
+
+        assert_eq!(result.teams.len(), 2);
+        assert_eq!(result.substitutes.len(), 1);
+        assert_eq!(result.substitutes[0].id, "p0");
+    }
+
+    /// A locked team of three new players, named if `name` is given
+    fn locked_team(ids: [&str; 3], name: Option<&str>) -> LockedTeam {
+        let [goalie, midfield, forward] = ids.map(|id| StringPlayer {
+            id: id.to_string(),
+            ..Default::default()
+        });
+
+        LockedTeam {
+            players: StringTeam::new(goalie, midfield, forward),
+            name: name.map(|x| x.to_string()),
+            fixed_roles: false,
+        }
+    }
+
+    #[test]
+    fn rejects_players_in_more_than_one_locked_team() {
+        let pairer = Pairer::new(Vec::new(), PairingOptions::default()).with_locked_teams(vec![
+            locked_team(["X", "y", "z"], None),
+            locked_team(["u", "v", "x"], None),
+        ]);
+
+        assert_eq!(
+            pairer.pair(),
+            Err(PairingError::LockedTeamsOverlap(vec!["x".to_string()]))
+        );
+    }
+
+    #[test]
+    fn generated_names_skip_the_names_of_locked_teams() {
+        let options = PairingOptions {
+            seed: Some(0),
+            ..Default::default()
+        };
+
+        let result = Pairer::new(seven_players_with_a_volunteer(), options)
+            .with_locked_teams(vec![locked_team(["x", "y", "z"], Some("Team 2"))])
+            .pair()
+            .unwrap();
+
+        let mut names: Vec<&str> = result.teams.iter().map(|x| x.name.as_str()).collect();
+        names.sort();
+
+        assert_eq!(names, ["Team 2", "Team 3", "Team 4"]);
+    }
+
+    /// Pairs the same players twice with the given options, returning both results as json
+    fn pair_twice(options: PairingOptions) -> (String, String) {
+        let players = generate_players(20, &mut StdRng::seed_from_u64(1));
+
+        let pair = || {
+            let result = Pairer::new(players.clone(), options.clone())
+                .pair()
+                .unwrap();
+
+            serde_json::to_string(&result).unwrap()
+        };
+
+        (pair(), pair())
+    }
+
+    /// Options which also generate team names, so naming takes part in the randomness
+    fn seeded_options(solver: Solver) -> PairingOptions {
+        let words = |prefix: &str| (0..10).map(|x| format!("{}{}", prefix, x)).collect();
+
+        PairingOptions {
+            solver,
+            seed: Some(42),
+            annealing: AnnealingOptions {
+                restarts: 4,
+                iterations: 20_000,
+                ..Default::default()
+            },
+            adjectives: words("adjective"),
+            nouns: words("noun"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_result() {
+        for solver in [Solver::Greedy, Solver::Annealing] {
+            let (first, second) = pair_twice(seeded_options(solver));
+
+            assert_eq!(first, second, "{}", solver);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn thread_count_does_not_change_the_result() {
+        for solver in [Solver::Greedy, Solver::Annealing] {
+            let results = [1, 4].map(|threads| {
+                pair_twice(PairingOptions {
+                    threads: Some(threads),
+                    ..seeded_options(solver)
+                })
+            });
+
+            assert_eq!(results[0], results[1], "{}", solver);
+            assert_eq!(results[0].0, results[0].1, "{}", solver);
+        }
+    }
+
+    #[test]
+    fn requests_which_cant_be_kept_stay_in_the_result() {
+        let mut players = seven_players_with_a_volunteer();
+
+        players[0].required_teammates = vec!["X".to_string(), "ghost".to_string()];
+
+        let options = PairingOptions {
+            seed: Some(0),
+            ..Default::default()
+        };
+
+        let result = Pairer::new(players, options)
+            .with_locked_teams(vec![locked_team(["x", "y", "z"], None)])
+            .pair()
+            .unwrap();
+
+        let requests: Vec<(&str, &str, bool)> = result
+            .unsatisfied_requests
+            .iter()
+            .map(|x| (x.player.as_str(), x.teammate.as_str(), x.required))
+            .collect();
+
+        assert_eq!(requests, [("p0", "X", true), ("p0", "ghost", true)]);
+    }
+
+    #[test]
+    fn rejects_more_than_three_players_who_have_to_play_together() {
+        let mut players = seven_players_with_a_volunteer();
+
+        for (i, player) in players.iter_mut().take(4).enumerate() {
+            player.required_teammates = vec![format!("p{}", (i + 1) % 4)];
+        }
+
+        for solver in [Solver::Greedy, Solver::Exact, Solver::Annealing] {
+            let options = PairingOptions {
+                solver,
+                ..Default::default()
+            };
+
+            assert_eq!(
+                Pairer::new(players.clone(), options).pair(),
+                Err(PairingError::OversizedGroup(
+                    (0..4).map(|x| format!("p{}", x)).collect()
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn solver_failures_suggest_another_solver() {
+        for (solver, other) in [
+            (Solver::Greedy, Solver::Annealing),
+            (Solver::Exact, Solver::Annealing),
+            (Solver::Annealing, Solver::Greedy),
+        ] {
+            let message = PairingError::SolverFailed(solver, vec!["p0".to_string()]).to_string();
+
+            assert!(message.starts_with(&format!("The {} solver", solver)));
+            assert!(
+                message.ends_with(&format!("--solver {}", other)),
+                "{}",
+                message
+            );
+        }
+
+        let message = PairingError::OversizedGroup(vec!["p0".to_string()]).to_string();
+        assert!(!message.contains("--solver"));
+    }
+}
@@ -0,0 +1,208 @@
+use std::{fmt::Display, iter::Sum, str::FromStr};
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The names of the ranks, indexed by their value
+const RANK_NAMES: [&str; 12] = [
+    "Gold",
+    "Low Platinum",
+    "Mid Platinum",
+    "High Platinum",
+    "Low Diamond",
+    "Mid Diamond",
+    "High Diamond",
+    "Low Challenger",
+    "Mid Challenger",
+    "High Challenger",
+    "Omega",
+    "Pro League",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+/// A player's rank, between 0 and 11
+///
+/// Written as a number, or as its name:
+///
+/// Gold (or below) = 0
+/// Low Platinum = 1
+/// (...)
+/// Low Diamond = 4
+/// (...)
+/// Low Challenger = 7
+/// (...)
+/// Omega = 10
+/// Pro League = 11
+///
+/// Names are case insensitive, and Plat, Dia, Chall and PL work too.
+pub struct Rank(u8);
+
+impl Rank {
+    pub const MAX: Rank = Rank(11);
+
+    /// Returns the rank with the given value, if it is between 0 and 11
+    pub fn new(value: u8) -> Option<Self> {
+        (value <= Self::MAX.0).then_some(Self(value))
+    }
+
+    pub fn value(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<Rank> for f64 {
+    fn from(rank: Rank) -> Self {
+        rank.0 as f64
+    }
+}
+
+impl Sum<Rank> for f64 {
+    fn sum<I: Iterator<Item = Rank>>(iter: I) -> Self {
+        iter.map(f64::from).sum()
+    }
+}
+
+impl Display for Rank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", RANK_NAMES[self.0 as usize])
+    }
+}
+
+impl FromStr for Rank {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "unknown rank '{}', expected a number between 0 and 11 or a name like Low Diamond, Mid Challenger, Omega or Pro League",
+                s
+            )
+        };
+
+        if let Ok(value) = s.trim().parse::<u8>() {
+            return Self::new(value).ok_or_else(error);
+        }
+
+        let words: Vec<String> = s.split_whitespace().map(|x| x.to_lowercase()).collect();
+        let words: Vec<&str> = words.iter().map(|x| x.as_str()).collect();
+
+        let (level, tier) = match words.as_slice() {
+            [level @ ("low" | "mid" | "high"), tier] => (Some(*level), *tier),
+            ["pro", "league"] => (None, "pl"),
+            [tier] => (None, *tier),
+            _ => return Err(error()),
+        };
+
+        let first = match tier {
+            "plat" | "platinum" => 1,
+            "dia" | "diamond" => 4,
+            "chall" | "challenger" => 7,
+            "gold" if level.is_none() => return Ok(Rank(0)),
+            "omega" if level.is_none() => return Ok(Rank(10)),
+            "pl" if level.is_none() => return Ok(Rank(11)),
+            _ => return Err(error()),
+        };
+
+        match level {
+            Some("low") => Ok(Rank(first)),
+            Some("mid") => Ok(Rank(first + 1)),
+            Some("high") => Ok(Rank(first + 2)),
+            _ => Err(format!(
+                "rank '{}' needs to say whether it's Low, Mid or High",
+                s
+            )),
+        }
+    }
+}
+
+impl Serialize for Rank {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rank {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RankVisitor)
+    }
+}
+
+struct RankVisitor;
+
+impl Visitor<'_> for RankVisitor {
+    type Value = Rank;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a rank between 0 and 11, or its name")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        u8::try_from(v)
+            .ok()
+            .and_then(Rank::new)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        u8::try_from(v)
+            .ok()
+            .and_then(Rank::new)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Signed(v), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for value in 0..=Rank::MAX.value() {
+            let rank = Rank::new(value).unwrap();
+
+            assert_eq!(rank.to_string().parse::<Rank>(), Ok(rank));
+            assert_eq!(value.to_string().parse::<Rank>(), Ok(rank));
+        }
+    }
+
+    #[test]
+    fn parses_short_and_odd_names() {
+        for (name, value) in [
+            ("gold", 0),
+            ("Low Plat", 1),
+            ("  mid   PLATINUM ", 2),
+            ("High Dia", 6),
+            ("low chall", 7),
+            ("OMEGA", 10),
+            ("PL", 11),
+            ("pro league", 11),
+        ] {
+            assert_eq!(name.parse::<Rank>(), Ok(Rank(value)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_ranks() {
+        for name in ["12", "-1", "Diamond", "Mid Gold", "Low Omega", "Silver", ""] {
+            assert!(name.parse::<Rank>().is_err(), "{}", name);
+        }
+
+        assert!(Rank::new(12).is_none());
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let rank: Rank = serde_json::from_str("\"Mid Diamond\"").unwrap();
+        assert_eq!(rank, Rank(5));
+        assert_eq!(serde_json::from_str::<Rank>("5").unwrap(), rank);
+        assert_eq!(serde_json::to_string(&rank).unwrap(), "\"Mid Diamond\"");
+
+        assert!(serde_json::from_str::<Rank>("12").is_err());
+        assert!(serde_json::from_str::<Rank>("-1").is_err());
+        assert!(serde_json::from_str::<Rank>("4.5").is_err());
+    }
+}
@@ -0,0 +1,497 @@
+use crate::config::{RatingConfig, RuleKind, ScoringConfig, ScoringRule};
+use crate::rank::Rank;
+use crate::types::{Attributes, Player, Role, RolePreferences, StringPlayer, StringTeam, Team};
+
+/// A player, whether they refer to other players by index or by name
+///
+/// Everything teams are scored on is defined once here, for both [`Player`] and
+/// [`StringPlayer`].
+pub trait PlayerLike {
+    /// How players are referred to, e.g. in blacklists
+    type Id: PartialEq;
+
+    fn id(&self) -> &Self::Id;
+    fn rank(&self) -> Rank;
+    fn rating(&self) -> Option<f64>;
+    fn role_preferences(&self) -> &RolePreferences;
+    /// Roles the player never plays
+    fn banned_roles(&self) -> &[Role];
+    /// The only role the player plays, if any
+    fn locked_role(&self) -> Option<Role>;
+    fn blacklisted_players(&self) -> &[Self::Id];
+    /// Players this player would like to be on a team with
+    fn preferred_teammates(&self) -> &[Self::Id];
+    fn attributes(&self) -> &Attributes;
+
+    /// Returns whether the player is the one referred to by the id
+    fn is(&self, id: &Self::Id) -> bool {
+        self.id() == id
+    }
+
+    /// Returns the player's values for an attribute, none if they don't have it
+    fn attribute(&self, name: &str) -> &[String] {
+        self.attributes().get(name).map_or(&[], |x| x.as_slice())
+    }
+
+    /// Returns how strong the player is, in the configured unit
+    fn strength(&self, config: &RatingConfig) -> f64 {
+        config.strength(self.rank(), self.rating())
+    }
+
+    /// Returns whether the player may play the role at all
+    fn may_play(&self, role: Role) -> bool {
+        !self.banned_roles().contains(&role) && self.locked_role().is_none_or(|x| x == role)
+    }
+
+    /// Gets how much the player would like to play as the role.
+    ///
+    /// If the player never plays the role (it's banned, or they are locked to another), returns 0
+    /// If the role is their first choice, returns 3
+    /// If the role is their second choice, returns 2
+    /// If the role is their last choice, returns 1
+    ///
+    /// Preferences given as weights are somewhere between 0 and 3
+    fn get_preference_of_role(&self, role: Role) -> f64 {
+        if !self.may_play(role) {
+            return 0.0;
+        }
+
+        self.role_preferences().weight(role)
+    }
+
+    /// Returns whether either player blacklisted the other
+    fn is_blacklisted_with(&self, other: &Self) -> bool {
+        self.blacklisted_players().iter().any(|x| other.is(x))
+            || other.blacklisted_players().iter().any(|x| self.is(x))
+    }
+
+    /// Returns whether the player would like to play with the other player
+    fn prefers(&self, other: &Self) -> bool {
+        self.preferred_teammates().iter().any(|x| other.is(x))
+    }
+}
+
+/// Three players in their roles
+pub trait TeamLike {
+    type Player: PlayerLike;
+
+    /// Returns the players as goalie, midfield, forward
+    fn players(&self) -> [&Self::Player; 3];
+
+    /// Returns the summed strength of the players, in the unit of `config.unit`
+    fn strength(&self, config: &RatingConfig) -> f64 {
+        self.players().iter().map(|x| x.strength(config)).sum()
+    }
+
+    /// Returns every pair of players on the team where one hates the other
+    fn blacklisted_pairs(&self) -> impl Iterator<Item = [&Self::Player; 2]> {
+        let [goalie, midfield, forward] = self.players();
+
+        [[goalie, midfield], [goalie, forward], [midfield, forward]]
+            .into_iter()
+            .filter(|[a, b]| a.is_blacklisted_with(b))
+    }
+
+    /// Returns every player who plays with someone they would like to, along with that teammate
+    fn preferred_pairs(&self) -> impl Iterator<Item = [&Self::Player; 2]> {
+        let [goalie, midfield, forward] = self.players();
+
+        [
+            [goalie, midfield],
+            [goalie, forward],
+            [midfield, goalie],
+            [midfield, forward],
+            [forward, goalie],
+            [forward, midfield],
+        ]
+        .into_iter()
+        .filter(|[player, teammate]| player.prefers(teammate))
+    }
+}
+
+/// A term of a team's score
+///
+/// Implement it for any [`TeamLike`] to score id-based and string-based teams the same way.
+pub trait Scorer<T: TeamLike> {
+    fn score(&self, team: &T, target_rank: f64, config: &ScoringConfig) -> f64;
+}
+
+/// How much the players like their roles
+pub struct RoleScorer;
+
+impl RoleScorer {
+    /// Returns what a single player playing the role adds
+    pub fn player_score<P: PlayerLike>(player: &P, role: Role, config: &ScoringConfig) -> f64 {
+        player.get_preference_of_role(role) * config.role_weight
+    }
+}
+
+impl<T: TeamLike> Scorer<T> for RoleScorer {
+    fn score(&self, team: &T, _target_rank: f64, config: &ScoringConfig) -> f64 {
+        team.players()
+            .into_iter()
+            .zip(Role::ALL)
+            .map(|(player, role)| Self::player_score(player, role, config))
+            .sum()
+    }
+}
+
+/// How close the team is to the target rank, see `rank_curve`
+pub struct RankScorer;
+
+impl<T: TeamLike> Scorer<T> for RankScorer {
+    fn score(&self, team: &T, target_rank: f64, config: &ScoringConfig) -> f64 {
+        config.rank_score(target_rank - team.strength(&config.rating))
+    }
+}
+
+/// What players who hate eachother being on the team costs, negative
+pub struct BlacklistScorer;
+
+impl<T: TeamLike> Scorer<T> for BlacklistScorer {
+    fn score(&self, team: &T, _target_rank: f64, config: &ScoringConfig) -> f64 {
+        -(team.blacklisted_pairs().count() as f64) * config.blacklist_weight
+    }
+}
+
+/// What players getting to play with someone they wanted to adds
+pub struct PreferredTeammateScorer;
+
+impl<T: TeamLike> Scorer<T> for PreferredTeammateScorer {
+    fn score(&self, team: &T, _target_rank: f64, config: &ScoringConfig) -> f64 {
+        team.preferred_pairs().count() as f64 * config.preferred_teammate_weight
+    }
+}
+
+impl<T: TeamLike> Scorer<T> for ScoringRule {
+    fn score(&self, team: &T, _target_rank: f64, _config: &ScoringConfig) -> f64 {
+        let [a, b, c] = team.players().map(|x| x.attribute(&self.attribute));
+
+        let shares = |a: &[String], b: &[String]| a.iter().any(|x| b.contains(x));
+        let same = |a: &[String], b: &[String]| {
+            a.iter().all(|x| b.contains(x)) && b.iter().all(|x| a.contains(x))
+        };
+
+        let times = match self.kind {
+            RuleKind::Shared => a.iter().any(|x| b.contains(x) && c.contains(x)) as usize,
+            RuleKind::Pairs => [(a, b), (a, c), (b, c)]
+                .iter()
+                .filter(|(x, y)| shares(x, y))
+                .count(),
+            // Players without the attribute can't tell us whether they are distinct
+            RuleKind::Distinct => {
+                let known = [a, b, c].iter().filter(|x| !x.is_empty()).count();
+
+                (known >= 2 && !(shares(a, b) || shares(a, c) || shares(b, c))) as usize
+            }
+            RuleKind::Mixed => !(same(a, b) && same(a, c)) as usize,
+        };
+
+        times as f64 * self.weight
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// A term of the team score
+pub enum Component<'a> {
+    Roles,
+    Rank,
+    Blacklist,
+    PreferredTeammates,
+    /// A rule from the scoring config
+    Rule(&'a ScoringRule),
+}
+
+impl Component<'_> {
+    /// The components every team is scored on, whatever the config
+    pub const BUILT_IN: [Component<'static>; 4] = [
+        Component::Roles,
+        Component::Rank,
+        Component::Blacklist,
+        Component::PreferredTeammates,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Component::Roles => "roles",
+            Component::Rank => "rank",
+            Component::Blacklist => "blacklist",
+            Component::PreferredTeammates => "preferred teammates",
+            Component::Rule(rule) => &rule.name,
+        }
+    }
+}
+
+impl<T: TeamLike> Scorer<T> for Component<'_> {
+    fn score(&self, team: &T, target_rank: f64, config: &ScoringConfig) -> f64 {
+        match self {
+            Component::Roles => RoleScorer.score(team, target_rank, config),
+            Component::Rank => RankScorer.score(team, target_rank, config),
+            Component::Blacklist => BlacklistScorer.score(team, target_rank, config),
+            Component::PreferredTeammates => {
+                PreferredTeammateScorer.score(team, target_rank, config)
+            }
+            Component::Rule(rule) => rule.score(team, target_rank, config),
+        }
+    }
+}
+
+/// Returns everything teams are scored on: the built-in components, then the config's rules
+pub fn components(config: &ScoringConfig) -> impl Iterator<Item = Component<'_>> {
+    Component::BUILT_IN
+        .into_iter()
+        .chain(config.rules.iter().map(Component::Rule))
+}
+
+/// All components summed, which is what teams are scored with
+pub struct TeamScorer;
+
+impl<T: TeamLike> Scorer<T> for TeamScorer {
+    fn score(&self, team: &T, target_rank: f64, config: &ScoringConfig) -> f64 {
+        components(config)
+            .map(|x| x.score(team, target_rank, config))
+            .sum()
+    }
+}
+
+impl PlayerLike for Player {
+    type Id = usize;
+
+    fn id(&self) -> &usize {
+        &self.id
+    }
+
+    fn rank(&self) -> Rank {
+        self.rank
+    }
+
+    fn rating(&self) -> Option<f64> {
+        self.rating
+    }
+
+    fn role_preferences(&self) -> &RolePreferences {
+        &self.role_preferences
+    }
+
+    fn banned_roles(&self) -> &[Role] {
+        &self.banned_roles
+    }
+
+    fn locked_role(&self) -> Option<Role> {
+        self.locked_role
+    }
+
+    fn blacklisted_players(&self) -> &[usize] {
+        &self.blacklisted_players
+    }
+
+    fn preferred_teammates(&self) -> &[usize] {
+        &self.preferred_teammates
+    }
+
+    fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+}
+
+impl PlayerLike for StringPlayer {
+    type Id = String;
+
+    fn id(&self) -> &String {
+        &self.id
+    }
+
+    /// Names are looked up ignoring case, like when they are interned
+    fn is(&self, id: &String) -> bool {
+        self.id.to_lowercase() == id.to_lowercase()
+    }
+
+    fn rank(&self) -> Rank {
+        self.rank
+    }
+
+    fn rating(&self) -> Option<f64> {
+        self.rating
+    }
+
+    fn role_preferences(&self) -> &RolePreferences {
+        &self.role_preferences
+    }
+
+    fn banned_roles(&self) -> &[Role] {
+        &self.banned_roles
+    }
+
+    fn locked_role(&self) -> Option<Role> {
+        self.locked_role
+    }
+
+    fn blacklisted_players(&self) -> &[String] {
+        &self.blacklisted_players
+    }
+
+    fn preferred_teammates(&self) -> &[String] {
+        &self.preferred_teammates
+    }
+
+    fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+}
+
+/// Three players as goalie, midfield, forward, so they can be scored without building a team
+impl<P: PlayerLike> TeamLike for [&P; 3] {
+    type Player = P;
+
+    fn players(&self) -> [&P; 3] {
+        *self
+    }
+}
+
+impl TeamLike for Team {
+    type Player = Player;
+
+    fn players(&self) -> [&Player; 3] {
+        [&self.goalie, &self.midfield, &self.forward]
+    }
+}
+
+impl TeamLike for StringTeam {
+    type Player = StringPlayer;
+
+    fn players(&self) -> [&StringPlayer; 3] {
+        [&self.goalie, &self.midfield, &self.forward]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::IndexTeam;
+
+    /// A team whose players have the given values for "language", an empty list meaning the
+    /// player doesn't have the attribute
+    fn team(values: [&[&str]; 3]) -> StringTeam {
+        let [goalie, midfield, forward] = values.map(|values| {
+            let mut attributes = Attributes::new();
+
+            if !values.is_empty() {
+                attributes.insert(
+                    "language".to_string(),
+                    values.iter().map(|x| x.to_string()).collect(),
+                );
+            }
+
+            StringPlayer {
+                attributes,
+                ..Default::default()
+            }
+        });
+
+        StringTeam::new(goalie, midfield, forward)
+    }
+
+    #[test]
+    fn rules_apply_by_kind() {
+        use RuleKind::*;
+
+        // How often each rule applies
+        let cases: [(RuleKind, [&[&str]; 3], usize); 20] = [
+            (Shared, [&["en"], &["en"], &["en"]], 1),
+            (Shared, [&["en", "de"], &["de"], &["fr", "de"]], 1),
+            (Shared, [&["en"], &["en"], &["de"]], 0),
+            (Shared, [&["en"], &["en"], &[]], 0),
+            (Shared, [&[], &[], &[]], 0),
+            (Pairs, [&["en"], &["en"], &["en"]], 3),
+            (Pairs, [&["en"], &["en"], &["de"]], 1),
+            (Pairs, [&["en", "de"], &["en"], &["de"]], 2),
+            (Pairs, [&["en"], &["en"], &[]], 1),
+            (Pairs, [&["en"], &["de"], &["fr"]], 0),
+            (Distinct, [&["a"], &["b"], &["c"]], 1),
+            (Distinct, [&["a"], &["b"], &[]], 1),
+            (Distinct, [&["a", "b"], &["b"], &["c"]], 0),
+            (Distinct, [&["a"], &[], &[]], 0),
+            (Distinct, [&[], &[], &[]], 0),
+            (Mixed, [&["en"], &["en"], &["en"]], 0),
+            (Mixed, [&["en", "de"], &["de", "en"], &["en", "de"]], 0),
+            (Mixed, [&["en"], &["en"], &["en", "de"]], 1),
+            (Mixed, [&["en"], &["en"], &[]], 1),
+            (Mixed, [&[], &[], &[]], 0),
+        ];
+
+        for (kind, values, times) in cases {
+            let rule = ScoringRule {
+                name: "language".to_string(),
+                attribute: "language".to_string(),
+                kind,
+                weight: 2.5,
+            };
+
+            assert_eq!(
+                rule.score(&team(values), 0.0, &ScoringConfig::default()),
+                times as f64 * 2.5,
+                "{:?} {:?}",
+                kind,
+                values
+            );
+        }
+    }
+
+    #[test]
+    fn team_types_score_the_same() {
+        let player = |id: &str, rank: u8, roles: &[Role]| StringPlayer {
+            id: id.to_string(),
+            rank: Rank::new(rank).unwrap(),
+            role_preferences: RolePreferences::from_ranking(roles),
+            ..Default::default()
+        };
+
+        let mut string_players = vec![
+            player("a", 3, &[Role::Goalie, Role::Forward]),
+            player("b", 7, &[Role::Forward]),
+            player("c", 9, &[Role::Midfield, Role::Goalie, Role::Forward]),
+        ];
+
+        // Names are matched ignoring case everywhere
+        string_players[0].blacklisted_players = vec!["B".to_string()];
+        string_players[2].preferred_teammates = vec!["a".to_string()];
+        string_players[1].banned_roles = vec![Role::Midfield];
+        string_players[0]
+            .attributes
+            .insert("language".to_string(), vec!["en".to_string()]);
+        string_players[2]
+            .attributes
+            .insert("language".to_string(), vec!["en".to_string()]);
+
+        let config = ScoringConfig {
+            rules: vec![ScoringRule {
+                name: "language".to_string(),
+                attribute: "language".to_string(),
+                kind: RuleKind::Pairs,
+                weight: 2.0,
+            }],
+            ..Default::default()
+        };
+
+        let (_, players) = crate::pairer::intern_players(&string_players, false);
+
+        for [goalie, midfield, forward] in [[0, 1, 2], [2, 0, 1], [1, 2, 0]] {
+            let index_team = IndexTeam::new(goalie, midfield, forward);
+            let team = index_team.to_team(&players);
+            let string_team = StringTeam::new(
+                string_players[goalie].clone(),
+                string_players[midfield].clone(),
+                string_players[forward].clone(),
+            );
+
+            let score = index_team.score(&players, 19.0, &config);
+
+            assert_eq!(team.score(19.0, &config), score);
+            assert_eq!(string_team.score(19.0, &config), score);
+
+            // The blacklist and the preferred teammate both count
+            assert_eq!(BlacklistScorer.score(&string_team, 19.0, &config), -20.0);
+            assert_eq!(PreferredTeammateScorer.score(&team, 19.0, &config), 5.0);
+        }
+    }
+}
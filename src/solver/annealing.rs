@@ -0,0 +1,199 @@
+use color_print::cprintln;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ScoringConfig;
+use crate::scoring::PlayerLike;
+use crate::types::{IndexTeam, Player};
+
+use super::{best_role_assignment, ObjectiveTracker, Solver, SolverReport};
+
+/// How much worse a team missing a required teammate counts while annealing
+const REQUIREMENT_PENALTY: f64 = 1000.0;
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
+#[serde(default)]
+/// Options for the simulated annealing solver
+pub struct AnnealingOptions {
+    /// How many times we start over from a random split of players
+    pub restarts: usize,
+    /// How many swaps we try per restart
+    pub iterations: usize,
+    /// How willing we are to accept a worse split at the start
+    pub start_temperature: f64,
+    /// How willing we are to accept a worse split at the end
+    pub end_temperature: f64,
+}
+
+impl Default for AnnealingOptions {
+    fn default() -> Self {
+        Self {
+            restarts: 8,
+            iterations: 200_000,
+            start_temperature: 10.0,
+            end_temperature: 0.01,
+        }
+    }
+}
+
+/// Returns the players of the `team`th team in a split
+fn members(order: &[usize], team: usize) -> [usize; 3] {
+    [order[team * 3], order[team * 3 + 1], order[team * 3 + 2]]
+}
+
+/// Splits players into teams with simulated annealing.
+///
+/// Each restart starts from a random split, then repeatedly swaps two random players,
+/// keeping the swap if it improves the [objective](crate::config::Objective) and sometimes even
+/// when it doesn't, less and less often as the temperature drops. The best split seen over all
+/// restarts is returned, on ties the one with the higher summed score.
+///
+/// All randomness comes from `rng`, so the same seed always gives the same teams.
+///
+/// Splits with teams the other solvers never pick, e.g. missing a required teammate, are
+/// heavily penalized, but not ruled out.
+///
+/// If the number of players is not a multiple of three, the leftover players are not in any team.
+pub fn solve_annealing<R: Rng>(
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+    options: &AnnealingOptions,
+    rng: &mut R,
+    verbose: bool,
+) -> (Vec<IndexTeam>, SolverReport) {
+    let team_count = players.len() / 3;
+
+    let trio_score = |members: [usize; 3]| {
+        let (team, score) = best_role_assignment(members, players, target_rank, config);
+
+        // Teams which aren't allowed break a hard constraint, so make that hurt a lot
+        if !team.is_allowed(players, config) {
+            score - REQUIREMENT_PENALTY
+        } else {
+            score
+        }
+    };
+
+    let trio_strength = |members: [usize; 3]| -> f64 {
+        members
+            .iter()
+            .map(|x| players[*x].strength(&config.rating))
+            .sum()
+    };
+
+    let mut best_order: Vec<usize> = (0..players.len()).collect();
+    let mut best_total = f64::NEG_INFINITY;
+    let mut best_sum = f64::NEG_INFINITY;
+
+    // Whether a split is better than the best one so far, ties go to the higher summed score
+    let is_best = |total: f64, sum: f64, best_total: f64, best_sum: f64| {
+        total > best_total + 1e-9 || (total >= best_total - 1e-9 && sum > best_sum + 1e-9)
+    };
+
+    for restart in 0..options.restarts.max(1) {
+        let mut order: Vec<usize> = (0..players.len()).collect();
+        order.shuffle(rng);
+
+        let scores: Vec<f64> = (0..team_count)
+            .map(|x| trio_score(members(&order, x)))
+            .collect();
+        let strengths: Vec<f64> = (0..team_count)
+            .map(|x| trio_strength(members(&order, x)))
+            .collect();
+
+        let mut tracker = ObjectiveTracker::new(scores, strengths, config);
+
+        let mut total = tracker.value();
+
+        if is_best(total, tracker.score_sum(), best_total, best_sum) {
+            best_total = total;
+            best_sum = tracker.score_sum();
+            best_order = order.clone();
+        }
+
+        // Nothing to swap
+        if team_count == 0 {
+            break;
+        }
+
+        for iteration in 0..options.iterations {
+            let progress = iteration as f64 / options.iterations as f64;
+            let temperature = options.start_temperature
+                * (options.end_temperature / options.start_temperature).powf(progress);
+
+            let a = rng.gen_range(0..players.len());
+            let b = rng.gen_range(0..players.len());
+
+            // Leftover players all end up in the "team" past the last one
+            let (team_a, team_b) = (a / 3, b / 3);
+
+            if team_a == team_b {
+                continue;
+            }
+
+            order.swap(a, b);
+
+            // The teams the swap changes, as (team, score, strength)
+            let mut changes = [(0, 0.0, 0.0); 2];
+            let mut change_count = 0;
+
+            for team in [team_a, team_b] {
+                if team < team_count {
+                    let members = members(&order, team);
+
+                    changes[change_count] = (team, trio_score(members), trio_strength(members));
+                    change_count += 1;
+                }
+            }
+
+            let changes = &changes[..change_count];
+
+            let new_total = tracker.value_with(changes);
+            let delta = new_total - total;
+
+            if delta >= 0.0 || rng.gen::<f64>() < (delta / temperature).exp() {
+                for (team, score, strength) in changes.iter().copied() {
+                    tracker.set(team, score, strength);
+                }
+
+                total = new_total;
+
+                if is_best(total, tracker.score_sum(), best_total, best_sum) {
+                    best_total = total;
+                    best_sum = tracker.score_sum();
+                    best_order = order.clone();
+                }
+            } else {
+                // Undo the swap
+                order.swap(a, b);
+            }
+        }
+
+        if verbose {
+            cprintln!(
+                "<green>Annealing restart {}:</green> <blue>best score {:.2}</blue>",
+                restart,
+                best_total
+            );
+        }
+    }
+
+    let teams: Vec<IndexTeam> = (0..team_count)
+        .map(|x| best_role_assignment(members(&best_order, x), players, target_rank, config).0)
+        .collect();
+
+    let report = SolverReport {
+        solver: Solver::Annealing,
+        score: teams
+            .iter()
+            .map(|x| x.score(players, target_rank, config))
+            .sum(),
+        optimal: false,
+        upper_bound: None,
+        local_search_improvement: None,
+    };
+
+    (teams, report)
+}
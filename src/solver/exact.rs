@@ -0,0 +1,407 @@
+use std::time::{Duration, Instant};
+
+use color_print::cprintln;
+
+use crate::config::ScoringConfig;
+use crate::types::{IndexTeam, Player};
+
+use super::{enumerate_teams, Solver, SolverReport};
+
+/// How many search nodes we visit between checking the time limit
+const NODES_BETWEEN_TIME_CHECKS: u64 = 1024;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+/// Three players, along with their best role assignment and its score
+pub struct Trio {
+    /// Indexes into the players slice, sorted
+    pub members: [usize; 3],
+    /// The best role assignment of the three players
+    pub team: IndexTeam,
+    pub score: f64,
+}
+
+/// Computes the best role assignment for every unordered trio of players.
+///
+/// When we look for the best partition only the best assignment of each trio can ever matter.
+///
+/// The trios are sorted by descending score.
+pub fn best_trios(players: &[Player], target_rank: f64, config: &ScoringConfig) -> Vec<Trio> {
+    let all: Vec<usize> = (0..players.len()).collect();
+
+    enumerate_teams(players, &all, target_rank, config, 1, None)
+        .into_iter()
+        .map(|(team, score)| {
+            let mut members = team.members();
+            members.sort();
+
+            Trio {
+                members,
+                team,
+                score,
+            }
+        })
+        .collect()
+}
+
+/// Finds the partition of players into teams with the highest summed score.
+///
+/// Uses branch-and-bound, where the bound for each player left is the best trio they could
+/// still be in. If `time_limit` runs out, returns the best teams found so far, along with
+/// how far they may be from the optimum.
+///
+/// If the number of players is not a multiple of three, the leftover players are not in any team.
+/// If there's no way to put all other players in teams, no teams are returned.
+pub fn solve_exact(
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+    time_limit: Option<Duration>,
+    verbose: bool,
+) -> (Vec<IndexTeam>, SolverReport) {
+    let started = Instant::now();
+
+    let trios = best_trios(players, target_rank, config);
+
+    let mut trios_of_player = vec![Vec::new(); players.len()];
+    let mut trios_led_by_player = vec![Vec::new(); players.len()];
+
+    for (i, trio) in trios.iter().enumerate() {
+        for member in trio.members {
+            trios_of_player[member].push(i);
+        }
+
+        // Members are sorted, so the first one is the lowest
+        trios_led_by_player[trio.members[0]].push(i);
+    }
+
+    let mut search = Search {
+        trios: &trios,
+        trios_of_player,
+        trios_led_by_player,
+        assigned: vec![false; players.len()],
+        current: Vec::new(),
+        current_score: 0.0,
+        skips_left: players.len() % 3,
+        best: Vec::new(),
+        best_score: f64::NEG_INFINITY,
+        deadline: time_limit.map(|x| started + x),
+        nodes: 0,
+        timed_out: false,
+    };
+
+    // Start off with a greedy solution, so we have something to prune with
+    let mut taken = vec![false; players.len()];
+
+    for (i, trio) in trios.iter().enumerate() {
+        if trio.members.iter().any(|x| taken[*x]) {
+            continue;
+        }
+
+        for member in trio.members {
+            taken[member] = true;
+        }
+
+        search.best.push(i);
+    }
+
+    // Greedy picks can leave out more players than we may, e.g. with impossible required
+    // teammates, which is no solution at all
+    if players.len() - search.best.len() * 3 <= players.len() % 3 {
+        search.best_score = search.best.iter().map(|x| trios[*x].score).sum();
+    } else {
+        search.best.clear();
+    }
+
+    let root_upper_bound = search.upper_bound();
+
+    if verbose {
+        cprintln!(
+            "<green>Exact:</green> <blue>{} trios, greedy score {:.2}, upper bound {:.2}</blue>",
+            trios.len(),
+            search.best_score,
+            root_upper_bound
+        );
+    }
+
+    search.dfs(0);
+
+    // Without any way to split up the players there is nothing to be optimal
+    let found = search.best_score.is_finite();
+    let optimal = !search.timed_out && found;
+
+    if verbose {
+        if optimal {
+            cprintln!(
+                "<green>Exact:</green> <blue>proved optimal after {} nodes</blue>",
+                search.nodes
+            );
+        } else if !search.timed_out {
+            cprintln!(
+                "<yellow>Exact: there is no way to put every player on a team after {} nodes</yellow>",
+                search.nodes
+            );
+        } else {
+            cprintln!(
+                "<yellow>Exact: time limit reached after {} nodes, using the best teams found so far</yellow>",
+                search.nodes
+            );
+        }
+    }
+
+    let report = SolverReport {
+        solver: Solver::Exact,
+        score: if found { search.best_score } else { 0.0 },
+        optimal,
+        upper_bound: if optimal {
+            Some(search.best_score)
+        } else if root_upper_bound.is_finite() {
+            Some(root_upper_bound)
+        } else {
+            None
+        },
+        local_search_improvement: None,
+    };
+
+    let teams = search.best.iter().map(|x| trios[*x].team).collect();
+
+    (teams, report)
+}
+
+/// State of the branch-and-bound search
+struct Search<'a> {
+    trios: &'a [Trio],
+    /// For each player, the trios they are in, by descending score
+    trios_of_player: Vec<Vec<usize>>,
+    /// For each player, the trios where they are the lowest index member, by descending score
+    trios_led_by_player: Vec<Vec<usize>>,
+    /// Whether each player is already in a team (or left out)
+    assigned: Vec<bool>,
+    current: Vec<usize>,
+    current_score: f64,
+    /// How many more players we may leave out of a team
+    skips_left: usize,
+    best: Vec<usize>,
+    best_score: f64,
+    deadline: Option<Instant>,
+    nodes: u64,
+    timed_out: bool,
+}
+
+impl Search<'_> {
+    /// Returns whether all members of the trio are still free
+    fn is_free(&self, trio: usize) -> bool {
+        self.trios[trio].members.iter().all(|x| !self.assigned[*x])
+    }
+
+    /// An upper bound on the score the players left can still add.
+    ///
+    /// Each player contributes a third of the best trio they could still be in.
+    fn upper_bound(&self) -> f64 {
+        let mut forced_skips = 0;
+        let mut values = Vec::new();
+
+        for player in 0..self.assigned.len() {
+            if self.assigned[player] {
+                continue;
+            }
+
+            let best_trio = self.trios_of_player[player]
+                .iter()
+                .find(|x| self.is_free(**x));
+
+            match best_trio {
+                Some(trio) => values.push(self.trios[*trio].score / 3.0),
+                None => forced_skips += 1,
+            }
+        }
+
+        if forced_skips > self.skips_left {
+            return f64::NEG_INFINITY;
+        }
+
+        // Whoever is left out contributes nothing, at best that is the worst players
+        values.sort_by(|x, y| x.total_cmp(y));
+
+        values.iter().skip(self.skips_left - forced_skips).sum()
+    }
+
+    fn dfs(&mut self, from: usize) {
+        self.nodes += 1;
+
+        if self.nodes.is_multiple_of(NODES_BETWEEN_TIME_CHECKS)
+            && self.deadline.is_some_and(|x| Instant::now() >= x)
+        {
+            self.timed_out = true;
+        }
+
+        if self.timed_out {
+            return;
+        }
+
+        // Players before this one are always assigned already
+        let Some(player) = (from..self.assigned.len()).find(|x| !self.assigned[*x]) else {
+            if self.current_score > self.best_score {
+                self.best_score = self.current_score;
+                self.best = self.current.clone();
+            }
+            return;
+        };
+
+        if self.current_score + self.upper_bound() <= self.best_score + 1e-9 {
+            return;
+        }
+
+        for i in 0..self.trios_led_by_player[player].len() {
+            let trio = self.trios_led_by_player[player][i];
+
+            if !self.is_free(trio) {
+                continue;
+            }
+
+            self.set_assigned(trio, true);
+            self.current.push(trio);
+            self.current_score += self.trios[trio].score;
+
+            self.dfs(player + 1);
+
+            self.current_score -= self.trios[trio].score;
+            self.current.pop();
+            self.set_assigned(trio, false);
+
+            if self.timed_out {
+                return;
+            }
+        }
+
+        // Leave this player out of a team
+        if self.skips_left > 0 {
+            self.assigned[player] = true;
+            self.skips_left -= 1;
+
+            self.dfs(player + 1);
+
+            self.skips_left += 1;
+            self.assigned[player] = false;
+        }
+    }
+
+    fn set_assigned(&mut self, trio: usize, assigned: bool) {
+        for member in self.trios[trio].members {
+            self.assigned[member] = assigned;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::config::BlacklistMode;
+    use crate::generate::generate_players;
+    use crate::pairer::intern_players;
+    use crate::scoring::PlayerLike;
+    use crate::solver::role_assignments;
+
+    /// The best summed score of any split of the players into teams, trying every split
+    fn brute_force(
+        players: &[Player],
+        assigned: &mut [bool],
+        skips_left: usize,
+        target_rank: f64,
+        config: &ScoringConfig,
+    ) -> f64 {
+        let Some(first) = (0..players.len()).find(|x| !assigned[*x]) else {
+            return 0.0;
+        };
+
+        let mut best = f64::NEG_INFINITY;
+
+        assigned[first] = true;
+
+        if skips_left > 0 {
+            best = best.max(brute_force(
+                players,
+                assigned,
+                skips_left - 1,
+                target_rank,
+                config,
+            ));
+        }
+
+        for b in (first + 1)..players.len() {
+            for c in (b + 1)..players.len() {
+                if assigned[b] || assigned[c] {
+                    continue;
+                }
+
+                let team_score = role_assignments([first, b, c], players, target_rank, config)
+                    .iter()
+                    .filter(|x| x.0.is_allowed(players, config))
+                    .map(|x| x.1)
+                    .reduce(f64::max);
+
+                let Some(team_score) = team_score else {
+                    continue;
+                };
+
+                assigned[b] = true;
+                assigned[c] = true;
+
+                let rest = brute_force(players, assigned, skips_left, target_rank, config);
+                best = best.max(team_score + rest);
+
+                assigned[b] = false;
+                assigned[c] = false;
+            }
+        }
+
+        assigned[first] = false;
+
+        best
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        for blacklist_mode in [BlacklistMode::Soft, BlacklistMode::Hard] {
+            let config = ScoringConfig {
+                blacklist_mode,
+                ..Default::default()
+            };
+
+            for count in 9..=12 {
+                for seed in 0..3 {
+                    let string_players = generate_players(count, &mut StdRng::seed_from_u64(seed));
+                    let (_, players) = intern_players(&string_players, false);
+
+                    let target_rank = players
+                        .iter()
+                        .map(|x| x.strength(&config.rating))
+                        .sum::<f64>()
+                        / count as f64
+                        * 3.0;
+
+                    let (teams, report) = solve_exact(&players, target_rank, &config, None, false);
+
+                    let expected = brute_force(
+                        &players,
+                        &mut vec![false; count],
+                        count % 3,
+                        target_rank,
+                        &config,
+                    );
+
+                    let score: f64 = teams
+                        .iter()
+                        .map(|x| x.score(&players, target_rank, &config))
+                        .sum();
+
+                    assert!(report.optimal);
+                    assert_eq!(teams.len(), count / 3);
+                    assert!((score - expected).abs() < 1e-9, "{} vs {}", score, expected);
+                    assert!((report.score - expected).abs() < 1e-9);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,418 @@
+use color_print::cprintln;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::config::ScoringConfig;
+use crate::types::{IndexTeam, Player};
+
+use super::{best_role_assignment, enumerate_teams, role_assignments, Solver, SolverReport};
+
+/// Makes a result by just going from the top -- greedy
+///
+/// Once there are less than `lookahead_threshold` possible teams left, we also take into
+/// account the teams a pick would leave behind.
+///
+/// Each trio of players is considered with its best `assignments_per_trio` role assignments.
+///
+/// At most `max_candidates` possible teams are kept in memory at once. If they run out
+/// before the players do, new ones are generated from the players who are left.
+///
+/// Players who are left over and can't be on a team together are fitted in by
+/// [splitting up](fit_in_leftovers) picked teams.
+pub fn solve_greedy(
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+    lookahead_threshold: usize,
+    assignments_per_trio: usize,
+    max_candidates: usize,
+    verbose: bool,
+) -> (Vec<IndexTeam>, SolverReport) {
+    let mut teams: Vec<IndexTeam> = Vec::new();
+
+    let mut taken = vec![false; players.len()];
+
+    let mut phase = 0;
+
+    loop {
+        let remaining: Vec<usize> = (0..players.len()).filter(|x| !taken[*x]).collect();
+
+        if remaining.len() < 3 {
+            break;
+        }
+
+        let possible_teams = enumerate_teams(
+            players,
+            &remaining,
+            target_rank,
+            config,
+            assignments_per_trio,
+            Some(max_candidates),
+        );
+
+        // Whoever is left can't be put in any team together
+        if possible_teams.is_empty() {
+            break;
+        }
+
+        if verbose {
+            cprintln!(
+                "<green>Generated {} possible teams for {} players</green>",
+                possible_teams.len(),
+                remaining.len()
+            );
+        }
+
+        for team in select_teams_greedy(possible_teams, lookahead_threshold, &mut phase, verbose) {
+            for member in team.members() {
+                taken[member] = true;
+            }
+
+            teams.push(team);
+        }
+    }
+
+    fit_in_leftovers(&mut teams, &mut taken, players, target_rank, config);
+
+    let report = SolverReport {
+        solver: Solver::Greedy,
+        score: teams
+            .iter()
+            .map(|x| x.score(players, target_rank, config))
+            .sum(),
+        optimal: false,
+        upper_bound: None,
+        local_search_improvement: None,
+    };
+
+    (teams, report)
+}
+
+/// Fits players who didn't get a team into teams, as long as there are at least three of them.
+///
+/// Greedy picks can leave players who can't be on a team together, e.g. when none of them
+/// may play goalie. Each step splits a picked team and three of them into the two new teams
+/// which lose the least score. If no split works, a leftover player
+/// [takes the place](swap_in_leftover) of someone on a team who is easier to place.
+pub fn fit_in_leftovers(
+    teams: &mut Vec<IndexTeam>,
+    taken: &mut [bool],
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+) {
+    loop {
+        let remaining: Vec<usize> = (0..players.len()).filter(|x| !taken[*x]).collect();
+
+        if remaining.len() < 3 {
+            return;
+        }
+
+        let mut best: Option<(usize, IndexTeam, IndexTeam, f64)> = None;
+
+        for (i, team) in teams.iter().enumerate() {
+            let team_score = team.score(players, target_rank, config);
+
+            for a in 0..remaining.len() {
+                for b in (a + 1)..remaining.len() {
+                    for c in (b + 1)..remaining.len() {
+                        let six = [
+                            team.goalie,
+                            team.midfield,
+                            team.forward,
+                            remaining[a],
+                            remaining[b],
+                            remaining[c],
+                        ];
+
+                        // Every split into two trios, the first trio always has the first player
+                        for j in 1..6 {
+                            for k in (j + 1)..6 {
+                                let first = [six[0], six[j], six[k]];
+                                let rest: Vec<usize> =
+                                    (1..6).filter(|x| *x != j && *x != k).collect();
+                                let second = [six[rest[0]], six[rest[1]], six[rest[2]]];
+
+                                let (first, first_score) =
+                                    best_role_assignment(first, players, target_rank, config);
+                                let (second, second_score) =
+                                    best_role_assignment(second, players, target_rank, config);
+
+                                if !first.is_allowed(players, config)
+                                    || !second.is_allowed(players, config)
+                                {
+                                    continue;
+                                }
+
+                                let gain = first_score + second_score - team_score;
+
+                                if best.is_none_or(|x| gain > x.3) {
+                                    best = Some((i, first, second, gain));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let Some((i, first, second, _)) = best else {
+            // Nobody fits in yet, make the leftovers easier to place and try again
+            if swap_in_leftover(teams, taken, &remaining, players, target_rank, config) {
+                continue;
+            }
+
+            return;
+        };
+
+        for member in first.members().into_iter().chain(second.members()) {
+            taken[member] = true;
+        }
+
+        teams[i] = first;
+        teams.push(second);
+    }
+}
+
+/// Swaps a player who didn't get a team into a team, leaving out someone who can be on more
+/// teams instead.
+///
+/// E.g. when the leftovers only play goalie, one of them takes the place of a goalie who also
+/// plays other roles. Leftovers who have to play together are swapped in together. How easy
+/// players are to place is the number of allowed trios among the players on teams and the
+/// leftovers they could be in, so every swap makes the leftovers strictly easier to place and
+/// we can't go in circles. Of the swaps which help the most, the one which loses the least
+/// score is made.
+///
+/// Returns whether there was such a swap.
+fn swap_in_leftover(
+    teams: &mut [IndexTeam],
+    taken: &mut [bool],
+    remaining: &[usize],
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+) -> bool {
+    let pool: Vec<usize> = teams
+        .iter()
+        .flat_map(|x| x.members())
+        .chain(remaining.iter().copied())
+        .collect();
+
+    let fits = |members: [usize; 3]| {
+        IndexTeam::new(members[0], members[1], members[2]).is_allowed_trio(players, config)
+            && !role_assignments(members, players, target_rank, config)[0]
+                .0
+                .violates_roles(players)
+    };
+
+    // How many allowed trios each player could be in, by index into `players`
+    let mut placeable = vec![0_usize; players.len()];
+
+    for a in 0..pool.len() {
+        for b in (a + 1)..pool.len() {
+            for c in (b + 1)..pool.len() {
+                if fits([pool[a], pool[b], pool[c]]) {
+                    placeable[pool[a]] += 1;
+                    placeable[pool[b]] += 1;
+                    placeable[pool[c]] += 1;
+                }
+            }
+        }
+    }
+
+    // The slots of a team a group of one or two players can take
+    const SLOTS: [&[&[usize]]; 2] = [&[&[0], &[1], &[2]], &[&[1, 2], &[0, 2], &[0, 1]]];
+
+    // (team, new team, players left out, placeability gained, score gained)
+    let mut best: Option<(usize, IndexTeam, Vec<usize>, usize, f64)> = None;
+
+    for leftover in remaining.iter().copied() {
+        // Everyone who has to play with them is a leftover too, as picked teams are allowed
+        let player = &players[leftover];
+        let group: Vec<usize> = remaining
+            .iter()
+            .copied()
+            .filter(|x| {
+                *x == leftover
+                    || player.required_teammates.contains(&players[*x].id)
+                    || player.required_by.contains(&players[*x].id)
+            })
+            .collect();
+
+        // Three who have to play together would have been a team already
+        if group.len() > 2 || group[0] != leftover {
+            continue;
+        }
+
+        let group_placeable: usize = group.iter().map(|x| placeable[*x]).sum();
+
+        for (i, team) in teams.iter().enumerate() {
+            let team_score = team.score(players, target_rank, config);
+
+            for slots in SLOTS[group.len() - 1] {
+                let mut members = team.members();
+                let left_out: Vec<usize> = slots.iter().map(|x| members[*x]).collect();
+                let left_out_placeable: usize = left_out.iter().map(|x| placeable[*x]).sum();
+
+                if left_out_placeable <= group_placeable {
+                    continue;
+                }
+
+                for (slot, player) in slots.iter().zip(group.iter()) {
+                    members[*slot] = *player;
+                }
+
+                let (swapped, score) = best_role_assignment(members, players, target_rank, config);
+
+                if !swapped.is_allowed(players, config) {
+                    continue;
+                }
+
+                let gained = left_out_placeable - group_placeable;
+                let score_gain = score - team_score;
+
+                if best
+                    .as_ref()
+                    .is_none_or(|x| gained > x.3 || (gained == x.3 && score_gain > x.4))
+                {
+                    best = Some((i, swapped, left_out, gained, score_gain));
+                }
+            }
+        }
+    }
+
+    let Some((i, swapped, left_out, _, _)) = best else {
+        return false;
+    };
+
+    for member in swapped.members() {
+        taken[member] = true;
+    }
+
+    for player in left_out {
+        taken[player] = false;
+    }
+
+    teams[i] = swapped;
+
+    true
+}
+
+/// Greedily picks teams from the possible teams until none are left
+pub fn select_teams_greedy(
+    mut possible_teams: Vec<(IndexTeam, f64)>,
+    lookahead_threshold: usize,
+    phase: &mut usize,
+    verbose: bool,
+) -> Vec<IndexTeam> {
+    let mut teams = Vec::new();
+
+    while !possible_teams.is_empty() {
+        if verbose {
+            cprintln!(
+                "<green>Phase {}:</green> <blue>{} teams left</blue>",
+                phase,
+                possible_teams.len()
+            );
+        }
+
+        let team = if possible_teams.len() < lookahead_threshold {
+            // Assess all the teams in this phase, along with the teams left after we've taken it
+            //
+            // E.g. look one step ahead
+            let uber_score = |(team, score): &(IndexTeam, f64)| {
+                // Hypothetically, take this team and see which ones are left
+                let mut sum_of_scores = 0.0;
+                let mut teams_left = 0;
+
+                for (other_team, other_score) in possible_teams.iter() {
+                    if !other_team.shares_players(team) {
+                        sum_of_scores += other_score;
+                        teams_left += 1;
+                    }
+                }
+
+                // Compute the score of this + leftovers
+                let mut uber_score = *score;
+
+                if teams_left > 0 {
+                    uber_score += sum_of_scores / teams_left as f64;
+                }
+
+                uber_score
+            };
+
+            #[cfg(feature = "parallel")]
+            let uber_scores: Vec<f64> = possible_teams.par_iter().map(uber_score).collect();
+
+            #[cfg(not(feature = "parallel"))]
+            let uber_scores: Vec<f64> = possible_teams.iter().map(uber_score).collect();
+
+            // Take the best one, the first on ties
+            let mut best: Option<(IndexTeam, f64)> = None;
+
+            for (i, uber_score) in uber_scores.into_iter().enumerate() {
+                if best.is_none_or(|x| uber_score > x.1) {
+                    best = Some((possible_teams[i].0, uber_score));
+                }
+            }
+
+            best.unwrap().0
+        } else {
+            // Take the best team, for now don't bother
+            possible_teams[0].0
+        };
+
+        // Take this team, actually this time
+        possible_teams.retain(|other_possible_team| !other_possible_team.0.shares_players(&team));
+
+        teams.push(team);
+
+        *phase += 1;
+    }
+
+    teams
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::config::BlacklistMode;
+    use crate::generate::generate_players;
+    use crate::pairer::intern_players;
+
+    #[test]
+    fn fits_in_leftovers_who_have_to_play_in_pairs() {
+        // Three pairs who have to play together are left over, after the three players who
+        // could join them were put on a team together
+        let mut string_players = generate_players(9, &mut StdRng::seed_from_u64(0));
+
+        for player in string_players.iter_mut() {
+            player.blacklisted_players.clear();
+        }
+
+        for pair in [3, 5, 7] {
+            string_players[pair].required_teammates = vec![string_players[pair + 1].id.clone()];
+        }
+
+        let (_, players) = intern_players(&string_players, false);
+
+        let config = ScoringConfig {
+            blacklist_mode: BlacklistMode::Hard,
+            ..Default::default()
+        };
+
+        let mut teams = vec![IndexTeam::new(0, 1, 2)];
+        let mut taken = vec![false; players.len()];
+        taken[..3].fill(true);
+
+        fit_in_leftovers(&mut teams, &mut taken, &players, 0.0, &config);
+
+        assert!(taken.iter().all(|x| *x));
+        assert_eq!(teams.len(), 3);
+        assert!(teams.iter().all(|x| x.is_allowed(&players, &config)));
+    }
+}
@@ -0,0 +1,173 @@
+use color_print::cprintln;
+
+use crate::config::ScoringConfig;
+use crate::types::{IndexTeam, Player};
+
+use super::{best_role_assignment, ObjectiveTracker};
+
+/// The smallest improvement we still count as one, so rounding errors don't make us loop forever
+const MIN_IMPROVEMENT: f64 = 1e-9;
+
+/// Improves already picked teams by moving players around.
+///
+/// First reassigns roles within each team, then repeatedly tries swapping any two players
+/// on different teams (putting both teams into their best roles afterwards), keeping every
+/// swap that improves the [objective](crate::config::Objective) and doesn't separate required
+/// teammates. Stops once no swap helps anymore.
+///
+/// Returns how much the summed score improved, which is negative if the objective gave up
+/// some summed score for e.g. more even teams.
+pub fn improve_teams(
+    teams: &mut [IndexTeam],
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+    verbose: bool,
+) -> f64 {
+    let mut tracker = track_objective(teams, players, target_rank, config);
+
+    let start = tracker.score_sum();
+
+    // Reassign roles within each team, which never changes its strength
+    for (i, team) in teams.iter_mut().enumerate() {
+        let (best, score) = best_role_assignment(team.members(), players, target_rank, config);
+        let strength = best.strength(players, &config.rating);
+
+        if tracker.improves(&[(i, score, strength)], MIN_IMPROVEMENT) {
+            *team = best;
+            tracker.set(i, score, strength);
+        }
+    }
+
+    let mut pass = 0;
+    let mut improved = true;
+
+    while improved {
+        improved = false;
+
+        for i in 0..teams.len() {
+            for j in (i + 1)..teams.len() {
+                for slot_i in 0..3 {
+                    for slot_j in 0..3 {
+                        let mut members_i = teams[i].members();
+                        let mut members_j = teams[j].members();
+
+                        std::mem::swap(&mut members_i[slot_i], &mut members_j[slot_j]);
+
+                        let (team_i, score_i) =
+                            best_role_assignment(members_i, players, target_rank, config);
+                        let (team_j, score_j) =
+                            best_role_assignment(members_j, players, target_rank, config);
+
+                        let strength_i = team_i.strength(players, &config.rating);
+                        let strength_j = team_j.strength(players, &config.rating);
+
+                        let changes = [(i, score_i, strength_i), (j, score_j, strength_j)];
+
+                        if tracker.improves(&changes, MIN_IMPROVEMENT)
+                            && team_i.is_allowed(players, config)
+                            && team_j.is_allowed(players, config)
+                        {
+                            improved = true;
+
+                            teams[i] = team_i;
+                            teams[j] = team_j;
+                            tracker.set(i, score_i, strength_i);
+                            tracker.set(j, score_j, strength_j);
+                        }
+                    }
+                }
+            }
+        }
+
+        if verbose {
+            cprintln!(
+                "<green>Local search pass {}:</green> <blue>improved by {:.2} so far</blue>",
+                pass,
+                tracker.score_sum() - start
+            );
+        }
+
+        pass += 1;
+    }
+
+    tracker.score_sum() - start
+}
+
+/// Swaps substitutes into teams wherever that improves the
+/// [objective](crate::config::Objective).
+///
+/// This leaves out the players whose absence hurts the teams the least.
+/// `may_swap(substitute, player)` decides whether a substitute may take the place of
+/// a player on a team, who would become a substitute instead.
+///
+/// Returns how much the summed score improved.
+pub fn improve_substitutes<F: Fn(usize, usize) -> bool>(
+    teams: &mut [IndexTeam],
+    substitutes: &mut [usize],
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+    may_swap: F,
+) -> f64 {
+    let mut tracker = track_objective(teams, players, target_rank, config);
+
+    let start = tracker.score_sum();
+
+    let mut improved = true;
+
+    while improved {
+        improved = false;
+
+        for substitute in substitutes.iter_mut() {
+            for (i, team) in teams.iter_mut().enumerate() {
+                for slot in 0..3 {
+                    let mut members = team.members();
+
+                    if !may_swap(*substitute, members[slot]) {
+                        continue;
+                    }
+
+                    std::mem::swap(&mut members[slot], substitute);
+
+                    let (best, score) = best_role_assignment(members, players, target_rank, config);
+
+                    let strength = best.strength(players, &config.rating);
+
+                    if tracker.improves(&[(i, score, strength)], MIN_IMPROVEMENT)
+                        && best.is_allowed(players, config)
+                    {
+                        improved = true;
+
+                        *team = best;
+                        tracker.set(i, score, strength);
+                    } else {
+                        // Undo the swap
+                        std::mem::swap(&mut members[slot], substitute);
+                    }
+                }
+            }
+        }
+    }
+
+    tracker.score_sum() - start
+}
+
+/// Starts keeping track of the objective over the teams
+fn track_objective<'a>(
+    teams: &[IndexTeam],
+    players: &[Player],
+    target_rank: f64,
+    config: &'a ScoringConfig,
+) -> ObjectiveTracker<'a> {
+    let scores = teams
+        .iter()
+        .map(|x| x.score(players, target_rank, config))
+        .collect();
+    let strengths = teams
+        .iter()
+        .map(|x| x.strength(players, &config.rating))
+        .collect();
+
+    ObjectiveTracker::new(scores, strengths, config)
+}
@@ -0,0 +1,455 @@
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Objective, ScoringConfig};
+use crate::types::{IndexTeam, Player};
+
+pub mod annealing;
+pub mod exact;
+pub mod greedy;
+pub mod local_search;
+
+/// How many players' trios we generate at once before pruning
+const ENUMERATION_BLOCK_SIZE: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Which algorithm we use to pick teams
+pub enum Solver {
+    /// Greedily takes the best team, looking one step ahead once few teams are left
+    #[default]
+    Greedy,
+    /// Branch-and-bound search for the best possible partition of players into teams
+    Exact,
+    /// Simulated annealing from several random starting points, for large player pools
+    Annealing,
+}
+
+impl FromStr for Solver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "greedy" => Ok(Solver::Greedy),
+            "exact" => Ok(Solver::Exact),
+            "annealing" => Ok(Solver::Annealing),
+            _ => Err(format!(
+                "unknown solver '{}', expected greedy, exact or annealing",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for Solver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Solver::Greedy => write!(f, "greedy"),
+            Solver::Exact => write!(f, "exact"),
+            Solver::Annealing => write!(f, "annealing"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// Information about how a solver arrived at its teams
+pub struct SolverReport {
+    pub solver: Solver,
+    /// The summed score of the picked teams
+    pub score: f64,
+    /// Whether the picked teams are known to be the best possible ones
+    pub optimal: bool,
+    /// An upper bound on the best possible score, if the solver computes one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub upper_bound: Option<f64>,
+    /// How much the local search pass improved the score, if it ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub local_search_improvement: Option<f64>,
+}
+
+impl SolverReport {
+    /// Returns how far the score may be off the best possible score, relative to the score
+    ///
+    /// E.g. 0.05 means the best possible result is at most 5% better.
+    pub fn gap(&self) -> Option<f64> {
+        let upper_bound = self.upper_bound?;
+
+        if self.optimal {
+            return Some(0.0);
+        }
+
+        Some((upper_bound - self.score).max(0.0) / self.score.abs().max(f64::EPSILON))
+    }
+}
+
+/// Keeps track of the [objective](Objective) over a list of teams, so we can tell what
+/// changing a couple of them does without going over all of them again
+pub struct ObjectiveTracker<'a> {
+    config: &'a ScoringConfig,
+    scores: Vec<f64>,
+    strengths: Vec<f64>,
+    score_sum: f64,
+    strength_sum: f64,
+    square_sum: f64,
+    /// The teams with the lowest scores, lowest first. Only kept up to date for max-min
+    lowest: Vec<usize>,
+}
+
+impl<'a> ObjectiveTracker<'a> {
+    /// How many of the lowest scoring teams we keep track of, one more than
+    /// [`value_with`](Self::value_with) ever changes
+    const LOWEST_COUNT: usize = 3;
+
+    pub fn new(scores: Vec<f64>, strengths: Vec<f64>, config: &'a ScoringConfig) -> Self {
+        let mut tracker = Self {
+            config,
+            score_sum: scores.iter().sum(),
+            strength_sum: strengths.iter().sum(),
+            square_sum: strengths.iter().map(|x| x * x).sum(),
+            scores,
+            strengths,
+            lowest: Vec::new(),
+        };
+
+        tracker.find_lowest();
+
+        tracker
+    }
+
+    /// Returns the score of the `i`th team
+    pub fn score(&self, i: usize) -> f64 {
+        self.scores[i]
+    }
+
+    /// Returns the summed score of all teams
+    pub fn score_sum(&self) -> f64 {
+        self.score_sum
+    }
+
+    /// Returns the value of the objective
+    pub fn value(&self) -> f64 {
+        self.value_with(&[])
+    }
+
+    /// Returns the value of the objective if the given teams had the given scores and
+    /// strengths instead, as (team, score, strength). At most two teams may change
+    pub fn value_with(&self, changes: &[(usize, f64, f64)]) -> f64 {
+        let mut score_sum = self.score_sum;
+        let mut strength_sum = self.strength_sum;
+        let mut square_sum = self.square_sum;
+
+        for (i, score, strength) in changes.iter().copied() {
+            score_sum += score - self.scores[i];
+            strength_sum += strength - self.strengths[i];
+            square_sum += strength * strength - self.strengths[i] * self.strengths[i];
+        }
+
+        let count = self.scores.len();
+
+        match self.config.objective {
+            Objective::Sum => score_sum,
+            Objective::MaxMin => {
+                let unchanged = self
+                    .lowest
+                    .iter()
+                    .find(|x| !changes.iter().any(|change| change.0 == **x))
+                    .map(|x| self.scores[*x]);
+
+                let min = changes
+                    .iter()
+                    .map(|x| x.1)
+                    .chain(unchanged)
+                    .reduce(f64::min)
+                    .unwrap_or(0.0);
+
+                min * count as f64
+            }
+            Objective::MinVariance => {
+                score_sum
+                    - self
+                        .config
+                        .variance_penalty(count, strength_sum, square_sum)
+            }
+        }
+    }
+
+    /// Returns whether changing the given teams improves the objective, or leaves it as is
+    /// while improving the summed score
+    pub fn improves(&self, changes: &[(usize, f64, f64)], min_improvement: f64) -> bool {
+        let gain = self.value_with(changes) - self.value();
+        let sum_gain: f64 = changes.iter().map(|x| x.1 - self.scores[x.0]).sum();
+
+        // Ties have to be exact, otherwise a chain of tiny losses could make us go in circles
+        gain > min_improvement || (gain >= 0.0 && sum_gain > min_improvement)
+    }
+
+    /// Changes the score and strength of the `i`th team
+    pub fn set(&mut self, i: usize, score: f64, strength: f64) {
+        self.score_sum += score - self.scores[i];
+        self.strength_sum += strength - self.strengths[i];
+        self.square_sum += strength * strength - self.strengths[i] * self.strengths[i];
+
+        self.scores[i] = score;
+        self.strengths[i] = strength;
+
+        if self.config.objective == Objective::MaxMin {
+            let highest_lowest = self.lowest.last().map(|x| self.scores[*x]);
+
+            // Otherwise the lowest teams stay the same
+            if self.lowest.contains(&i) || highest_lowest.is_some_and(|x| score < x) {
+                self.find_lowest();
+            }
+        }
+    }
+
+    fn find_lowest(&mut self) {
+        if self.config.objective != Objective::MaxMin {
+            return;
+        }
+
+        let mut order: Vec<usize> = (0..self.scores.len()).collect();
+        order.sort_by(|x, y| self.scores[*x].total_cmp(&self.scores[*y]));
+        order.truncate(Self::LOWEST_COUNT);
+
+        self.lowest = order;
+    }
+}
+
+/// Returns every role assignment of three players with its score, by descending score.
+///
+/// Assignments where someone plays a role they never play come last. Ties keep the order
+/// goalie, midfield, forward as given.
+pub fn role_assignments(
+    members: [usize; 3],
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+) -> [(IndexTeam, f64); 6] {
+    let [a, b, c] = members;
+
+    let mut assignments = [
+        [a, b, c],
+        [a, c, b],
+        [b, a, c],
+        [b, c, a],
+        [c, a, b],
+        [c, b, a],
+    ]
+    .map(|[goalie, midfield, forward]| {
+        let team = IndexTeam::new(goalie, midfield, forward);
+
+        (team, team.score(players, target_rank, config))
+    });
+
+    // Stable, so ties keep their order
+    assignments.sort_by(|x, y| {
+        x.0.violates_roles(players)
+            .cmp(&y.0.violates_roles(players))
+            .then_with(|| y.1.total_cmp(&x.1))
+    });
+
+    assignments
+}
+
+/// Puts three players into the roles that give the best team score, only putting players in
+/// roles they never play if there's no other way
+pub fn best_role_assignment(
+    members: [usize; 3],
+    players: &[Player],
+    target_rank: f64,
+    config: &ScoringConfig,
+) -> (IndexTeam, f64) {
+    role_assignments(members, players, target_rank, config)[0]
+}
+
+/// Computes the possible teams out of the players at the given indexes, sorted by descending score.
+///
+/// Teams which aren't [allowed](IndexTeam::is_allowed) are never generated.
+///
+/// Each unordered trio of players is considered once, keeping only its best
+/// `assignments_per_trio` role assignments (at least one, at most all six). Roles can still
+/// be reassigned after picking, e.g. by the local search.
+///
+/// If `max_candidates` is given, only that many of the best teams are kept, so memory stays
+/// bounded for large player pools.
+pub fn enumerate_teams(
+    players: &[Player],
+    among: &[usize],
+    target_rank: f64,
+    config: &ScoringConfig,
+    assignments_per_trio: usize,
+    max_candidates: Option<usize>,
+) -> Vec<(IndexTeam, f64)> {
+    let assignments_per_trio = assignments_per_trio.clamp(1, 6);
+
+    let mut possible_teams = Vec::new();
+
+    // Drops all but the best max_candidates teams, in no particular order
+    let prune = |possible_teams: &mut Vec<(IndexTeam, f64)>, max_candidates: usize| {
+        if possible_teams.len() > max_candidates {
+            possible_teams.select_nth_unstable_by(max_candidates, |x, y| {
+                y.1.total_cmp(&x.1).then_with(|| x.0.cmp(&y.0))
+            });
+            possible_teams.truncate(max_candidates);
+        }
+    };
+
+    // All trios where the player at `a` is the first member
+    let trios_led_by = |a: usize| {
+        let mut trios = Vec::new();
+
+        for b in (a + 1)..among.len() {
+            for c in (b + 1)..among.len() {
+                let members = [among[a], among[b], among[c]];
+
+                // Never consider teams missing a required teammate or breaking a hard blacklist
+                let trio = IndexTeam::new(members[0], members[1], members[2]);
+
+                if !trio.is_allowed_trio(players, config) {
+                    continue;
+                }
+
+                // Nor players in roles they never play
+                let assignments = role_assignments(members, players, target_rank, config);
+
+                trios.extend(
+                    assignments
+                        .iter()
+                        .take(assignments_per_trio)
+                        .filter(|x| !x.0.violates_roles(players))
+                        .copied(),
+                );
+            }
+        }
+
+        trios
+    };
+
+    // Generate a block of players at a time, so we can prune in between
+    let mut block_start = 0;
+
+    while block_start < among.len() {
+        let block_end = (block_start + ENUMERATION_BLOCK_SIZE).min(among.len());
+
+        #[cfg(feature = "parallel")]
+        possible_teams.par_extend(
+            (block_start..block_end)
+                .into_par_iter()
+                .flat_map_iter(trios_led_by),
+        );
+
+        #[cfg(not(feature = "parallel"))]
+        possible_teams.extend((block_start..block_end).flat_map(trios_led_by));
+
+        // Prune once we have twice as many as we need, so it doesn't happen too often
+        if let Some(max_candidates) = max_candidates {
+            if possible_teams.len() >= max_candidates.saturating_mul(2) {
+                prune(&mut possible_teams, max_candidates);
+            }
+        }
+
+        block_start = block_end;
+    }
+
+    if let Some(max_candidates) = max_candidates {
+        prune(&mut possible_teams, max_candidates);
+    }
+
+    // Sort by descending score, ties by player indexes so pruning can't change the order
+    possible_teams.sort_by(|x, y| y.1.total_cmp(&x.1).then_with(|| x.0.cmp(&y.0)));
+
+    possible_teams
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+
+    use super::*;
+
+    /// Compares a tracked value with the value computed from scratch
+    fn assert_close(tracked: f64, expected: f64) {
+        assert!(
+            (tracked - expected).abs() < 1e-6 * expected.abs().max(1.0),
+            "{} vs {}",
+            tracked,
+            expected
+        );
+    }
+
+    /// A random score and strength for a team. Few distinct scores, so there are plenty of
+    /// ties among the lowest teams
+    fn random_team(rng: &mut StdRng) -> (f64, f64) {
+        (rng.gen_range(0..6) as f64, rng.gen_range(10.0..25.0))
+    }
+
+    #[test]
+    fn objective_tracker_matches_recomputing() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for objective in [Objective::Sum, Objective::MaxMin, Objective::MinVariance] {
+            let config = ScoringConfig {
+                objective,
+                ..Default::default()
+            };
+
+            for count in 1..=6 {
+                let (mut scores, mut strengths): (Vec<f64>, Vec<f64>) =
+                    (0..count).map(|_| random_team(&mut rng)).unzip();
+
+                let mut tracker = ObjectiveTracker::new(scores.clone(), strengths.clone(), &config);
+
+                for _ in 0..200 {
+                    // What changing one or two teams would do, without changing them
+                    let changed = rng.gen_range(1..=count.min(2));
+                    let changes: Vec<(usize, f64, f64)> = sample(&mut rng, count, changed)
+                        .into_iter()
+                        .map(|i| {
+                            let (score, strength) = random_team(&mut rng);
+                            (i, score, strength)
+                        })
+                        .collect();
+
+                    let mut changed_scores = scores.clone();
+                    let mut changed_strengths = strengths.clone();
+
+                    for (i, score, strength) in changes.iter().copied() {
+                        changed_scores[i] = score;
+                        changed_strengths[i] = strength;
+                    }
+
+                    assert_close(
+                        tracker.value_with(&changes),
+                        config.objective_value(&changed_scores, &changed_strengths),
+                    );
+
+                    // Then actually change one
+                    let i = rng.gen_range(0..count);
+                    let (score, strength) = random_team(&mut rng);
+
+                    tracker.set(i, score, strength);
+                    scores[i] = score;
+                    strengths[i] = strength;
+
+                    assert_close(tracker.value(), config.objective_value(&scores, &strengths));
+                    assert_close(tracker.score_sum(), scores.iter().sum());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solvers_are_written_like_they_are_parsed() {
+        for solver in [Solver::Greedy, Solver::Exact, Solver::Annealing] {
+            let written = serde_json::to_string(&solver).unwrap();
+
+            assert_eq!(written, format!("\"{}\"", solver));
+            assert_eq!(serde_json::from_str::<Solver>(&written).unwrap(), solver);
+        }
+    }
+}
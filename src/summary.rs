@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::ScoringConfig;
+use crate::scoring::{PlayerLike, RoleScorer, TeamLike};
+use crate::types::{Result, Role, StringPlayer, StringTeam};
+
+/// How many of the worst-off players the summary lists
+pub const WORST_OFF_COUNT: usize = 5;
+
+/// How many buckets of the rank sum distribution fit in `allowed_rank_deviation`
+const BUCKETS_PER_DEVIATION: f64 = 5.0;
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// How many teams have a rank sum in a range
+pub struct RankSumBucket {
+    /// The lowest rank sum in the range
+    pub from: f64,
+    /// The rank sum just past the range
+    pub to: f64,
+    pub teams: usize,
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// A player who got a worse deal than most
+pub struct WorstOff {
+    pub player: String,
+    pub team: String,
+    pub role: Role,
+    /// Which choice the role was for the player, 1 to 3, None if they didn't want it at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub choice: Option<usize>,
+    /// Teammates the player blacklisted, or who blacklisted them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub blacklisted_teammates: Vec<String>,
+    /// Preferred or required teammates who are on another team
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub missed_teammates: Vec<String>,
+    /// How well off the player is: their role score, plus their preferred teammates, minus
+    /// their blacklisted ones, weighted like team scores are
+    pub score: f64,
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// How satisfied players are with the teams, over the whole tournament
+pub struct Summary {
+    /// How many players play their favourite role
+    pub first_choice: usize,
+    /// How many players play the role they like second best
+    pub second_choice: usize,
+    /// How many players play the role they like least
+    pub third_choice: usize,
+    /// How many players play a role they didn't want at all
+    pub unwanted_role: usize,
+    /// How many pairs of players on the same team have one hating the other
+    pub blacklist_violations: usize,
+    /// How many players sit out as substitutes
+    pub substitutes: usize,
+    /// The rank sum we would like each team to have
+    pub target_rank: f64,
+    /// How many teams have which rank sums, lowest first. Empty ranges are left out
+    pub rank_sum_distribution: Vec<RankSumBucket>,
+    /// The players with the lowest scores, worst first
+    pub worst_off: Vec<WorstOff>,
+}
+
+impl Summary {
+    /// Summarizes how satisfied the players in a result are
+    pub fn of(result: &Result, target_rank: f64, config: &ScoringConfig) -> Self {
+        let mut summary = Self {
+            substitutes: result.substitutes.len(),
+            target_rank,
+            ..Default::default()
+        };
+
+        let mut players = Vec::new();
+
+        for team in result.teams.iter() {
+            let breakdown = team.players.breakdown(target_rank, config);
+
+            summary.blacklist_violations += breakdown.blacklisted_pairs.len();
+
+            for (player, role) in team.players.players().into_iter().zip(Role::ALL) {
+                let choice = role_choice(player, role);
+
+                match choice {
+                    Some(1) => summary.first_choice += 1,
+                    Some(2) => summary.second_choice += 1,
+                    Some(_) => summary.third_choice += 1,
+                    None => summary.unwanted_role += 1,
+                }
+
+                players.push(worst_off(
+                    player,
+                    &team.players,
+                    &team.name,
+                    role,
+                    choice,
+                    config,
+                ));
+            }
+        }
+
+        summary.rank_sum_distribution = rank_sum_distribution(
+            &result.team_strengths(config),
+            config.allowed_deviation() / BUCKETS_PER_DEVIATION,
+        );
+
+        // Stable, so ties stay in team order
+        players.sort_by(|x, y| x.score.total_cmp(&y.score));
+        players.truncate(WORST_OFF_COUNT);
+
+        summary.worst_off = players;
+
+        summary
+    }
+}
+
+/// Returns which choice a role is for a player, 1 to 3, or None if they don't want it at all.
+///
+/// See [`RolePreferences::choice_of`](crate::types::RolePreferences::choice_of).
+pub fn role_choice(player: &StringPlayer, role: Role) -> Option<usize> {
+    if player.get_preference_of_role(role) <= 0.0 {
+        return None;
+    }
+
+    Some(player.role_preferences.choice_of(role))
+}
+
+/// Describes how well off a player on a team is
+fn worst_off(
+    player: &StringPlayer,
+    team: &StringTeam,
+    team_name: &str,
+    role: Role,
+    choice: Option<usize>,
+    config: &ScoringConfig,
+) -> WorstOff {
+    let teammates: Vec<&StringPlayer> = team
+        .players()
+        .into_iter()
+        .filter(|x| x.id != player.id)
+        .collect();
+
+    let blacklisted_teammates: Vec<String> = teammates
+        .iter()
+        .filter(|x| player.is_blacklisted_with(x))
+        .map(|x| x.id.clone())
+        .collect();
+
+    let preferred = teammates.iter().filter(|x| player.prefers(x)).count();
+
+    let missed_teammates: Vec<String> = player
+        .preferred_teammates
+        .iter()
+        .chain(player.required_teammates.iter())
+        .filter(|x| !team.in_team(x))
+        .cloned()
+        .collect();
+
+    let score = RoleScorer::player_score(player, role, config)
+        + preferred as f64 * config.preferred_teammate_weight
+        - blacklisted_teammates.len() as f64 * config.blacklist_weight;
+
+    WorstOff {
+        player: player.id.clone(),
+        team: team_name.to_string(),
+        role,
+        choice,
+        blacklisted_teammates,
+        missed_teammates,
+        score,
+    }
+}
+
+/// Counts the rank sums per range of the given width
+fn rank_sum_distribution(rank_sums: &[f64], width: f64) -> Vec<RankSumBucket> {
+    let width = width.max(f64::EPSILON);
+
+    let mut buckets: Vec<RankSumBucket> = Vec::new();
+
+    let mut bucket_indexes: Vec<i64> = rank_sums
+        .iter()
+        .map(|x| (x / width).floor() as i64)
+        .collect();
+    bucket_indexes.sort();
+
+    for index in bucket_indexes {
+        let from = index as f64 * width;
+
+        match buckets.last_mut() {
+            Some(bucket) if bucket.from == from => bucket.teams += 1,
+            _ => buckets.push(RankSumBucket {
+                from,
+                to: from + width,
+                teams: 1,
+            }),
+        }
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RolePreferences;
+
+    #[test]
+    fn roles_left_out_are_the_last_choice() {
+        // Choices as goalie, midfield, forward
+        for (roles, expected) in [
+            (
+                &[Role::Goalie, Role::Midfield, Role::Forward][..],
+                [1, 2, 3],
+            ),
+            (&[Role::Forward, Role::Goalie], [2, 3, 1]),
+            (&[Role::Goalie], [1, 3, 3]),
+        ] {
+            let player = StringPlayer {
+                role_preferences: RolePreferences::from_ranking(roles),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                Role::ALL.map(|x| role_choice(&player, x)),
+                expected.map(Some),
+                "{:?}",
+                roles
+            );
+        }
+
+        let fill = StringPlayer::default();
+        assert_eq!(Role::ALL.map(|x| role_choice(&fill, x)), [Some(1); 3]);
+
+        let banned = StringPlayer {
+            role_preferences: RolePreferences::from_ranking(&[Role::Goalie]),
+            banned_roles: vec![Role::Midfield],
+            ..Default::default()
+        };
+        assert_eq!(
+            Role::ALL.map(|x| role_choice(&banned, x)),
+            [Some(1), None, Some(3)]
+        );
+    }
+}
@@ -1,140 +1,515 @@
 use rand::seq::SliceRandom;
 use rand::Rng;
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use std::collections::BTreeMap;
+use std::fmt::Display;
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Builder;
+
+use crate::config::{BlacklistMode, RatingConfig, ScoringConfig};
+use crate::rank::Rank;
+use crate::scoring::{components, Component, PlayerLike, RoleScorer, Scorer, TeamLike, TeamScorer};
+use crate::solver::SolverReport;
+use crate::summary::Summary;
+
+/// Attributes of a player by name, each with any number of values
+pub type Attributes = BTreeMap<String, Vec<String>>;
+
+/// Reads attributes whose values may also be a single string, number or bool instead of a list
+fn deserialize_attributes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Attributes, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Values {
+        One(String),
+        Bool(bool),
+        Number(f64),
+        Many(Vec<String>),
+    }
+
+    let attributes: BTreeMap<String, Values> = BTreeMap::deserialize(deserializer)?;
+
+    Ok(attributes
+        .into_iter()
+        .map(|(name, values)| {
+            let values = match values {
+                Values::One(value) => vec![value],
+                Values::Bool(value) => vec![value.to_string()],
+                Values::Number(value) => vec![value.to_string()],
+                Values::Many(values) => values,
+            };
+
+            (name, values)
+        })
+        .collect())
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
 /// A player with string ids and blacklisted players
 pub struct StringPlayer {
     pub id: String,
-    /// Between 0 and 11
-    ///
-    /// Low Plat = 1
-    /// (...)
-    /// Low Dia = 4
-    /// (...)
-    /// Low Challenger = 7
-    /// (...)
-    /// Omega = 10
-    /// PL = 11
-    pub rank: u8,
-    pub role_preferences: [Role; 3],
+    pub rank: Rank,
+    /// A more precise measure of strength than the rank, e.g. LP or elo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub rating: Option<f64>,
+    pub role_preferences: RolePreferences,
 
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(default)]
     pub blacklisted_players: Vec<String>,
-}
 
-impl StringPlayer {
-    /// Gets how much the player would like to play as the role.
-    ///
-    /// If the role is their first choice, returns 3
-    /// If the role is their second choice, returns 2
-    /// If the role is their last choice, returns 1
-    pub fn get_preference_of_role(&self, role: Role) -> f64 {
-        if role == self.role_preferences[0] {
-            return 3.0;
-        }
+    /// Players this player would like to be on a team with
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub preferred_teammates: Vec<String>,
 
-        if role == self.role_preferences[1] {
-            return 2.0;
-        }
-        // It must be the third, otherwise we messed up
-        else {
-            return 1.0;
-        }
-    }
+    /// Players this player has to be on a team with
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub required_teammates: Vec<String>,
 
-    pub fn create_random() -> StringPlayer {
-        let mut rng = rand::thread_rng();
+    /// Whether the player volunteers to sit out as a substitute if the teams don't add up
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    #[serde(default)]
+    pub substitute_ok: bool,
 
-        let rank: u8 = rng.gen_range(1..=11);
+    /// Roles the player never plays
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub banned_roles: Vec<Role>,
 
-        let mut role_preferences = [Role::Goalie, Role::Midfield, Role::Forward];
+    /// The only role the player plays, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub locked_role: Option<Role>,
 
-        role_preferences.shuffle(&mut rng);
+    /// Anything else scoring rules can look at, e.g. `{"language": ["en", "de"], "region": "EU"}`
+    #[serde(skip_serializing_if = "Attributes::is_empty")]
+    #[serde(default, deserialize_with = "deserialize_attributes")]
+    pub attributes: Attributes,
+}
+
+impl StringPlayer {
+    /// Creates a player with a random id, rank and role preferences
+    pub fn create_random<R: Rng>(rng: &mut R) -> StringPlayer {
+        let rank = Rank::new(rng.gen_range(1..=Rank::MAX.value())).unwrap_or_default();
+
+        let mut roles = Role::ALL;
+
+        roles.shuffle(rng);
 
         StringPlayer {
-            id: Uuid::new_v4().to_string(),
+            id: Builder::from_random_bytes(rng.gen())
+                .into_uuid()
+                .to_string(),
             rank,
-            role_preferences,
+            rating: None,
+            role_preferences: RolePreferences::from_ranking(&roles),
             blacklisted_players: Vec::new(),
+            preferred_teammates: Vec::new(),
+            required_teammates: Vec::new(),
+            substitute_ok: false,
+            banned_roles: Vec::new(),
+            locked_role: None,
+            attributes: Attributes::new(),
         }
     }
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
 /// A much more efficient representation of a player, with integer ids and blacklisted players
 ///
 /// The integer ids are indexes into a player names vector
 pub struct Player {
     pub id: usize,
-    /// Between 0 and 11
-    ///
-    /// Low Plat = 1
-    /// (...)
-    /// Low Dia = 4
-    /// (...)
-    /// Low Challenger = 7
-    /// (...)
-    /// Omega = 10
-    /// PL = 11
-    pub rank: u8,
-    pub role_preferences: [Role; 3],
+    pub rank: Rank,
+    /// A more precise measure of strength than the rank, e.g. LP or elo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub rating: Option<f64>,
+    pub role_preferences: RolePreferences,
 
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(default)]
     pub blacklisted_players: Vec<usize>,
+
+    /// Players this player would like to be on a team with
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub preferred_teammates: Vec<usize>,
+
+    /// Players this player has to be on a team with
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub required_teammates: Vec<usize>,
+
+    /// Players who have to be on a team with this player, the reverse of `required_teammates`
+    #[serde(skip)]
+    pub required_by: Vec<usize>,
+
+    /// Whether the player volunteers to sit out as a substitute if the teams don't add up
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    #[serde(default)]
+    pub substitute_ok: bool,
+
+    /// Roles the player never plays
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub banned_roles: Vec<Role>,
+
+    /// The only role the player plays, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub locked_role: Option<Role>,
+
+    /// Anything else scoring rules can look at, e.g. `{"language": ["en", "de"], "region": "EU"}`
+    #[serde(skip_serializing_if = "Attributes::is_empty")]
+    #[serde(default, deserialize_with = "deserialize_attributes")]
+    pub attributes: Attributes,
 }
 
 impl Player {
-    /// Gets how much the player would like to play as the role.
+    /// Converts self into the stringed player type
+    pub fn to_string_player(&self, player_names: &[String]) -> StringPlayer {
+        let name = player_names[self.id].clone();
+
+        let names = |ids: &[usize]| -> Vec<String> {
+            ids.iter().map(|x| player_names[*x].clone()).collect()
+        };
+
+        StringPlayer {
+            id: name,
+            rank: self.rank,
+            rating: self.rating,
+            role_preferences: self.role_preferences,
+            blacklisted_players: names(&self.blacklisted_players),
+            preferred_teammates: names(&self.preferred_teammates),
+            required_teammates: names(&self.required_teammates),
+            substitute_ok: self.substitute_ok,
+            banned_roles: self.banned_roles.clone(),
+            locked_role: self.locked_role,
+            attributes: self.attributes.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+pub enum Role {
+    #[default]
+    Goalie,
+    Forward,
+    Midfield,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Goalie, Role::Midfield, Role::Forward];
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+/// An entry in a list of role preferences
+enum RoleChoice {
+    Goalie,
+    Forward,
+    Midfield,
+    /// Any role which isn't listed before
+    Fill,
+}
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+/// How much a player would like to play each role, between 0 and 3
+///
+/// Can be given as:
+/// - A list of one to three roles, by preference. The first one is worth 3, the second 2
+///   and the third 1. `"Fill"` stands for every role which isn't listed before it, and roles
+///   which aren't listed at all are worth 1.
+/// - Just `"Fill"`, e.g. the player would like to play anything.
+/// - Weights per role, e.g. `{"Goalie": 0.9, "Forward": 0.1}`. These are scaled so the
+///   highest one is worth 3, roles left out are worth 0.
+///
+/// Roles listed again are ignored.
+pub struct RolePreferences {
+    /// Indexed like [`Role::ALL`]
+    weights: [f64; 3],
+}
+
+impl RolePreferences {
+    /// Every role is the first choice
+    pub fn fill() -> Self {
+        Self { weights: [3.0; 3] }
+    }
+
+    /// Builds preferences from roles by preference, the first one is the first choice
+    pub fn from_ranking(roles: &[Role]) -> Self {
+        let choices: Vec<RoleChoice> = roles.iter().map(|x| RoleChoice::from(*x)).collect();
+
+        Self::from_choices(&choices)
+    }
+
+    fn from_choices(choices: &[RoleChoice]) -> Self {
+        let mut weights: [Option<f64>; 3] = [None; 3];
+
+        // The first choice is worth 3, every next one that isn't listed again is worth 1 less
+        let mut weight = 3.0;
+
+        for choice in choices {
+            match choice.role() {
+                Some(role) if weights[Self::index(role)].is_some() => continue,
+                Some(role) => weights[Self::index(role)] = Some(weight),
+                None => {
+                    for x in weights.iter_mut() {
+                        x.get_or_insert(weight);
+                    }
+                }
+            }
+
+            weight -= 1.0;
+        }
+
+        Self {
+            weights: weights.map(|x| x.unwrap_or(1.0)),
+        }
+    }
+
+    /// Builds preferences from weights per role, the highest one is scaled to 3
     ///
-    /// If the role is their first choice, returns 3
-    /// If the role is their second choice, returns 2
-    /// If the role is their last choice, returns 1
-    pub fn get_preference_of_role(&self, role: Role) -> f64 {
-        if role == self.role_preferences[0] {
-            return 3.0;
+    /// Returns None if a weight is negative or not a number, or all of them are 0.
+    pub fn from_weights(weights: &[(Role, f64)]) -> Option<Self> {
+        let mut scaled = [0.0; 3];
+
+        for (role, weight) in weights {
+            if !weight.is_finite() || *weight < 0.0 {
+                return None;
+            }
+
+            scaled[Self::index(*role)] = *weight;
         }
 
-        if role == self.role_preferences[1] {
-            return 2.0;
+        let max = scaled.iter().copied().fold(0.0, f64::max);
+
+        if max <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            weights: scaled.map(|x| x / max * 3.0),
+        })
+    }
+
+    /// Gets how much the player would like to play the role
+    pub fn weight(&self, role: Role) -> f64 {
+        self.weights[Self::index(role)]
+    }
+
+    /// Returns the roles from most to least preferred, roles with the same weight in the
+    /// order of [`Role::ALL`]
+    pub fn ranking(&self) -> [Role; 3] {
+        let mut roles = Role::ALL;
+
+        // Stable, so ties keep their order
+        roles.sort_by(|x, y| self.weight(*y).total_cmp(&self.weight(*x)));
+
+        roles
+    }
+
+    /// Returns which choice the role is, 1 to 3
+    ///
+    /// Roles the player likes equally count as the better choice. A role is only the second
+    /// choice if it's worth as much as one, so roles left out of a ranking are the last choice
+    /// even if only one role was ranked.
+    pub fn choice_of(&self, role: Role) -> usize {
+        let weight = self.weight(role);
+
+        let better = Role::ALL
+            .iter()
+            .filter(|x| self.weight(**x) > weight)
+            .count();
+
+        // The choice the weight is worth, see `from_choices`
+        let by_weight = if weight >= 3.0 {
+            1
+        } else if weight >= 2.0 {
+            2
+        } else {
+            3
+        };
+
+        by_weight.max(better + 1)
+    }
+
+    /// Returns the list of roles by preference these preferences were read from, if they
+    /// can be read from one
+    ///
+    /// Roles are listed as long as they are worth what their place in the list is worth.
+    /// The roles left are either all worth as much as the next place, which makes them
+    /// `"Fill"`, or all worth 1, which they are when they're left out.
+    fn choices(&self) -> Option<Vec<RoleChoice>> {
+        let ranking = self.ranking();
+
+        let mut choices = Vec::new();
+
+        for (i, role) in ranking.iter().enumerate() {
+            let weight = 3.0 - i as f64;
+            let rest = &ranking[i..];
+
+            if rest.len() > 1 && rest.iter().all(|x| self.weight(*x) == weight) {
+                choices.push(RoleChoice::Fill);
+                break;
+            }
+
+            if self.weight(*role) == weight {
+                choices.push(RoleChoice::from(*role));
+            } else if rest.iter().all(|x| self.weight(*x) == 1.0) {
+                break;
+            } else {
+                return None;
+            }
         }
-        // It must be the third, otherwise we messed up
-        else {
-            return 1.0;
+
+        Some(choices)
+    }
+
+    fn index(role: Role) -> usize {
+        match role {
+            Role::Goalie => 0,
+            Role::Midfield => 1,
+            Role::Forward => 2,
         }
     }
+}
+
+impl Default for RolePreferences {
+    fn default() -> Self {
+        Self::fill()
+    }
+}
+
+impl Display for RolePreferences {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ranking = self.ranking();
 
-	 /// Converts self into the stringed player type
-	 pub fn to_string_player(&self, player_names: &Vec<String>) -> StringPlayer {
-		let name = player_names[self.id].clone();
+        write!(f, "{:?}", ranking[0])?;
 
-		let mut blacklisted_players = Vec::new();
+        for pair in ranking.windows(2) {
+            let separator = if self.weight(pair[0]) == self.weight(pair[1]) {
+                "="
+            } else {
+                ">"
+            };
 
-		for id in self.blacklisted_players.iter() {
-			blacklisted_players.push(player_names[*id].clone());
-		}
+            write!(f, " {} {:?}", separator, pair[1])?;
+        }
 
-		StringPlayer { id: name, rank: self.rank, role_preferences: self.role_preferences, blacklisted_players }
-	 }
+        Ok(())
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
-pub enum Role {
-    #[default]
-    Goalie,
-    Forward,
-    Midfield,
+impl From<Role> for RoleChoice {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Goalie => RoleChoice::Goalie,
+            Role::Forward => RoleChoice::Forward,
+            Role::Midfield => RoleChoice::Midfield,
+        }
+    }
 }
 
-pub const ROLE_WEIGHT: f64 = 1.0;
-pub const RANK_WEIGHT: f64 = 10.0;
-pub const BLACKLIST_WEIGHT: f64 = 20.0;
-pub const ALLOWED_RANK_DEVIATION: f64 = 5.0;
+impl RoleChoice {
+    /// Returns the role, or None for fill
+    fn role(self) -> Option<Role> {
+        match self {
+            RoleChoice::Goalie => Some(Role::Goalie),
+            RoleChoice::Forward => Some(Role::Forward),
+            RoleChoice::Midfield => Some(Role::Midfield),
+            RoleChoice::Fill => None,
+        }
+    }
+}
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+impl Serialize for RolePreferences {
+    /// Serializes as `"Fill"` or a list of roles if the preferences can be read from one, as
+    /// weights otherwise
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.choices() {
+            Some(choices) if choices == [RoleChoice::Fill] => {
+                return RoleChoice::Fill.serialize(serializer);
+            }
+            Some(choices) => return choices.serialize(serializer),
+            None => {}
+        }
+
+        let mut map = serializer.serialize_map(Some(3))?;
+
+        for role in Role::ALL {
+            map.serialize_entry(&role, &self.weight(role))?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RolePreferences {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(RolePreferencesVisitor)
+    }
+}
+
+struct RolePreferencesVisitor;
+
+impl<'de> Visitor<'de> for RolePreferencesVisitor {
+    type Value = RolePreferences;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "a list of one to three roles, \"Fill\" or weights per role"
+        )
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Self::Value, E> {
+        match v {
+            "Fill" => Ok(RolePreferences::fill()),
+            _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut choices = Vec::new();
+
+        while let Some(choice) = seq.next_element::<RoleChoice>()? {
+            choices.push(choice);
+        }
+
+        if choices.is_empty() || choices.len() > 3 {
+            return Err(de::Error::invalid_length(choices.len(), &self));
+        }
+
+        Ok(RolePreferences::from_choices(&choices))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut weights = Vec::new();
+
+        while let Some(entry) = map.next_entry::<Role, f64>()? {
+            weights.push(entry);
+        }
+
+        RolePreferences::from_weights(&weights).ok_or_else(|| {
+            de::Error::custom("role weights can't be negative, and at least one has to be above 0")
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
 /// A team with players with ids
 pub struct Team {
     pub goalie: Player,
@@ -151,70 +526,154 @@ impl Team {
         }
     }
 
-	 /// Returns whether or not the given player id is in the team
-	 pub fn in_team(&self, player_id: usize) -> bool {
-		self.goalie.id.eq(&player_id) || self.midfield.id.eq(&player_id) || self.forward.id.eq(&player_id)
-	 }
+    /// Returns whether or not the given player id is in the team
+    pub fn in_team(&self, player_id: usize) -> bool {
+        self.goalie.id.eq(&player_id)
+            || self.midfield.id.eq(&player_id)
+            || self.forward.id.eq(&player_id)
+    }
+
+    /// Returns whether or not the two teams have any players in common
+    pub fn shares_players(&self, other: &Team) -> bool {
+        self.in_team(other.goalie.id)
+            || self.in_team(other.midfield.id)
+            || self.in_team(other.forward.id)
+    }
 
     /// Scores the team.
     ///
     /// A higher score means we prefer the team composition more.
-    pub fn score(&self, target_rank: f64) -> f64 {
-        let mut score = 0.0;
-
-        score += self.goalie.get_preference_of_role(Role::Goalie) * ROLE_WEIGHT;
-        score += self.midfield.get_preference_of_role(Role::Midfield) * ROLE_WEIGHT;
-        score += self.forward.get_preference_of_role(Role::Forward) * ROLE_WEIGHT;
+    pub fn score(&self, target_rank: f64, config: &ScoringConfig) -> f64 {
+        TeamScorer.score(self, target_rank, config)
+    }
 
-        let mut rank_sum = 0;
-        rank_sum += self.goalie.rank;
-        rank_sum += self.midfield.rank;
-        rank_sum += self.forward.rank;
+    /// Converts self into the stringed team type
+    pub fn to_string_team(&self, player_names: &[String]) -> StringTeam {
+        let goalie = self.goalie.to_string_player(player_names);
+        let midfield = self.midfield.to_string_player(player_names);
+        let forward = self.forward.to_string_player(player_names);
 
-        let rank_diff = (target_rank - (rank_sum as f64)).abs();
+        StringTeam {
+            forward,
+            midfield,
+            goalie,
+        }
+    }
+}
 
-        if rank_diff <= ALLOWED_RANK_DEVIATION {
-            // Between 0 and 1, 1 if we are at the perfect rank, 0 if we are on the edge of the range
-            let multiplier = (ALLOWED_RANK_DEVIATION - rank_diff) / ALLOWED_RANK_DEVIATION;
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+/// A team which refers to its players by their index in a players slice
+///
+/// Much cheaper than [`Team`] to create and copy around, so it is what the solvers work with.
+pub struct IndexTeam {
+    pub goalie: usize,
+    pub midfield: usize,
+    pub forward: usize,
+}
 
-            score += RANK_WEIGHT * multiplier;
+impl IndexTeam {
+    pub fn new(goalie: usize, midfield: usize, forward: usize) -> Self {
+        Self {
+            goalie,
+            midfield,
+            forward,
         }
+    }
+
+    /// Returns the player indexes as goalie, midfield, forward
+    pub fn members(&self) -> [usize; 3] {
+        [self.goalie, self.midfield, self.forward]
+    }
+
+    /// Returns whether or not the given player index is in the team
+    pub fn in_team(&self, player_index: usize) -> bool {
+        self.goalie == player_index || self.midfield == player_index || self.forward == player_index
+    }
 
-        let mut has_players_who_hate_eachother = false;
+    /// Returns whether or not the two teams have any players in common
+    pub fn shares_players(&self, other: &IndexTeam) -> bool {
+        self.in_team(other.goalie) || self.in_team(other.midfield) || self.in_team(other.forward)
+    }
+
+    /// Returns whether a player on the team is missing a teammate they have to play with,
+    /// or is missing from the team of someone who has to play with them
+    pub fn violates_requirements(&self, players: &[Player]) -> bool {
+        let ids = self.members().map(|x| players[x].id);
 
-        has_players_who_hate_eachother |=
-            self.goalie.blacklisted_players.contains(&self.midfield.id);
-        has_players_who_hate_eachother |=
-            self.goalie.blacklisted_players.contains(&self.forward.id);
+        self.members().iter().any(|x| {
+            players[*x]
+                .required_teammates
+                .iter()
+                .chain(players[*x].required_by.iter())
+                .any(|required| !ids.contains(required))
+        })
+    }
 
-        has_players_who_hate_eachother |=
-            self.midfield.blacklisted_players.contains(&self.goalie.id);
-        has_players_who_hate_eachother |=
-            self.midfield.blacklisted_players.contains(&self.forward.id);
+    /// Returns how many pairs of players on the team have one hating the other
+    pub fn blacklisted_pairs(&self, players: &[Player]) -> usize {
+        self.trio(players).blacklisted_pairs().count()
+    }
 
-        has_players_who_hate_eachother |=
-            self.forward.blacklisted_players.contains(&self.midfield.id);
-        has_players_who_hate_eachother |=
-            self.forward.blacklisted_players.contains(&self.goalie.id);
+    /// Returns whether a player on the team plays a role they never play
+    pub fn violates_roles(&self, players: &[Player]) -> bool {
+        !players[self.goalie].may_play(Role::Goalie)
+            || !players[self.midfield].may_play(Role::Midfield)
+            || !players[self.forward].may_play(Role::Forward)
+    }
 
-        if has_players_who_hate_eachother {
-            score -= BLACKLIST_WEIGHT;
+    /// Returns whether the solvers may pick the team at all.
+    ///
+    /// Teams where someone plays a role they never play aren't, nor are the ones which
+    /// aren't an [allowed trio](IndexTeam::is_allowed_trio).
+    pub fn is_allowed(&self, players: &[Player], config: &ScoringConfig) -> bool {
+        !self.violates_roles(players) && self.is_allowed_trio(players, config)
+    }
+
+    /// Returns whether the three players may be on a team together, in whichever roles.
+    ///
+    /// Teams missing a required teammate never are, nor are teams with players who hate
+    /// eachother when blacklists are hard.
+    pub fn is_allowed_trio(&self, players: &[Player], config: &ScoringConfig) -> bool {
+        if self.violates_requirements(players) {
+            return false;
         }
 
-        score
+        match config.blacklist_mode {
+            BlacklistMode::Soft => true,
+            BlacklistMode::Hard => self.blacklisted_pairs(players) == 0,
+        }
     }
 
-	 /// Converts self into the stringed team type
-	 pub fn to_string_team(&self, player_names: &Vec<String>) -> StringTeam {
-		 let goalie = self.goalie.to_string_player(player_names);
-		 let midfield = self.midfield.to_string_player(player_names);
-		 let forward = self.forward.to_string_player(player_names);
+    /// Returns the summed strength of the players, in the unit of `config.unit`
+    pub fn strength(&self, players: &[Player], config: &RatingConfig) -> f64 {
+        self.trio(players).strength(config)
+    }
+
+    /// Scores the team.
+    ///
+    /// A higher score means we prefer the team composition more.
+    pub fn score(&self, players: &[Player], target_rank: f64, config: &ScoringConfig) -> f64 {
+        TeamScorer.score(&self.trio(players), target_rank, config)
+    }
+
+    /// Returns the players as goalie, midfield, forward, which can be scored like a team
+    pub fn trio<'a>(&self, players: &'a [Player]) -> [&'a Player; 3] {
+        self.members().map(|x| &players[x])
+    }
 
-		 StringTeam { forward, midfield, goalie }
-	 }
+    /// Converts self into the team type with players
+    pub fn to_team(&self, players: &[Player]) -> Team {
+        Team::new(
+            players[self.goalie].clone(),
+            players[self.midfield].clone(),
+            players[self.forward].clone(),
+        )
+    }
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
 /// A team with string players
 pub struct StringTeam {
     pub goalie: StringPlayer,
@@ -231,84 +690,208 @@ impl StringTeam {
         }
     }
 
-	 /// Returns whether or not the given player id is in the team
-	 pub fn in_team(&self, player_id: &String) -> bool {
-		self.goalie.id.eq(player_id) || self.midfield.id.eq(player_id) || self.forward.id.eq(player_id)
-	 }
+    /// Returns whether or not the given player id is in the team, ignoring case
+    pub fn in_team(&self, player_id: &String) -> bool {
+        self.players().iter().any(|x| x.is(player_id))
+    }
 
     /// Scores the team.
     ///
     /// A higher score means we prefer the team composition more.
-    pub fn score(&self, target_rank: f64) -> f64 {
-        let mut score = 0.0;
+    pub fn score(&self, target_rank: f64, config: &ScoringConfig) -> f64 {
+        TeamScorer.score(self, target_rank, config)
+    }
 
-        score += self.goalie.get_preference_of_role(Role::Goalie) * ROLE_WEIGHT;
-        score += self.midfield.get_preference_of_role(Role::Midfield) * ROLE_WEIGHT;
-        score += self.forward.get_preference_of_role(Role::Forward) * ROLE_WEIGHT;
+    /// Scores the team, keeping track of what the score is made up of
+    pub fn breakdown(&self, target_rank: f64, config: &ScoringConfig) -> ScoreBreakdown {
+        let roles = self
+            .players()
+            .into_iter()
+            .zip(Role::ALL)
+            .map(|(player, role)| RoleScore {
+                player: player.id.clone(),
+                role,
+                preference: player.get_preference_of_role(role),
+                score: RoleScorer::player_score(player, role, config),
+            })
+            .collect();
 
-        let mut rank_sum = 0;
-        rank_sum += self.goalie.rank;
-        rank_sum += self.midfield.rank;
-        rank_sum += self.forward.rank;
+        let rank_sum = self.strength(&config.rating);
 
-        let rank_diff = (target_rank - (rank_sum as f64)).abs();
+        let pair_ids = |[a, b]: [&StringPlayer; 2]| [a.id.clone(), b.id.clone()];
 
-        if rank_diff <= ALLOWED_RANK_DEVIATION {
-            // Between 0 and 1, 1 if we are at the perfect rank, 0 if we are on the edge of the range
-            let multiplier = (ALLOWED_RANK_DEVIATION - rank_diff) / ALLOWED_RANK_DEVIATION;
+        let mut breakdown = ScoreBreakdown {
+            roles,
+            rank_sum,
+            target_rank,
+            rank_deviation: rank_sum - target_rank,
+            blacklisted_pairs: self.blacklisted_pairs().map(pair_ids).collect(),
+            preferred_teammates: self.preferred_pairs().map(pair_ids).collect(),
+            ..Default::default()
+        };
 
-            score += RANK_WEIGHT * multiplier;
-        }
+        for component in components(config) {
+            let score = component.score(self, target_rank, config);
 
-        let mut has_players_who_hate_eachother = false;
+            match component {
+                // Already broken down per player
+                Component::Roles => {}
+                Component::Rank => breakdown.rank_score = score,
+                Component::Blacklist => breakdown.blacklist_penalty = -score,
+                Component::PreferredTeammates => breakdown.preferred_teammate_bonus = score,
+                Component::Rule(_) => breakdown.rules.push(RuleScore {
+                    name: component.name().to_string(),
+                    score,
+                }),
+            }
 
-        has_players_who_hate_eachother |=
-            self.goalie.blacklisted_players.contains(&self.midfield.id);
-        has_players_who_hate_eachother |=
-            self.goalie.blacklisted_players.contains(&self.forward.id);
+            breakdown.total += score;
+        }
 
-        has_players_who_hate_eachother |=
-            self.midfield.blacklisted_players.contains(&self.goalie.id);
-        has_players_who_hate_eachother |=
-            self.midfield.blacklisted_players.contains(&self.forward.id);
+        breakdown
+    }
+}
 
-        has_players_who_hate_eachother |=
-            self.forward.blacklisted_players.contains(&self.midfield.id);
-        has_players_who_hate_eachother |=
-            self.forward.blacklisted_players.contains(&self.goalie.id);
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// What a player's role adds to the score of their team
+pub struct RoleScore {
+    pub player: String,
+    pub role: Role,
+    /// How much the player would like to play the role, between 0 and 3
+    pub preference: f64,
+    /// The preference times `role_weight`
+    pub score: f64,
+}
 
-        if has_players_who_hate_eachother {
-            score -= BLACKLIST_WEIGHT;
-        }
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// What a scoring rule adds to the score of a team
+pub struct RuleScore {
+    pub name: String,
+    pub score: f64,
+}
 
-        score
-    }
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// What the score of a team is made up of, to explain why players landed where they did
+pub struct ScoreBreakdown {
+    /// What each player's role adds, as goalie, midfield, forward
+    pub roles: Vec<RoleScore>,
+    /// The summed strength of the players, in the unit of `rating.unit`
+    pub rank_sum: f64,
+    /// The rank sum we would like each team to have
+    pub target_rank: f64,
+    /// How far the rank sum is above the target, negative if below
+    pub rank_deviation: f64,
+    /// What the rank sum adds, see `rank_curve`
+    pub rank_score: f64,
+    /// Every pair of players on the team where one blacklisted the other
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub blacklisted_pairs: Vec<[String; 2]>,
+    /// How much the blacklisted pairs subtract
+    pub blacklist_penalty: f64,
+    /// Every player who plays with someone they would like to, along with that teammate
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub preferred_teammates: Vec<[String; 2]>,
+    /// How much the preferred teammates add
+    pub preferred_teammate_bonus: f64,
+    /// What each of the config's [rules](crate::config::ScoringRule) adds
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub rules: Vec<RuleScore>,
+    /// The score of the team
+    pub total: f64,
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
 /// A team with a unique name
 pub struct NamedTeam {
     pub players: StringTeam,
     pub name: String,
+    /// Whether the team was formed before pairing, see [`LockedTeam`]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    #[serde(default)]
+    pub locked: bool,
+    /// What the score of the team is made up of, see [`Result::fill_breakdowns`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub breakdown: Option<ScoreBreakdown>,
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// A team formed before pairing, e.g. three players who registered together
+///
+/// It ends up in the result as is, its players never take part in pairing.
+pub struct LockedTeam {
+    pub players: StringTeam,
+    /// The name of the team, a name is generated if None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Whether the players have to play the roles they are listed in, rather than their best ones
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    #[serde(default)]
+    pub fixed_roles: bool,
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// A players file which also has teams formed before pairing
+pub struct PlayersInput {
+    pub players: Vec<StringPlayer>,
+    #[serde(default)]
+    pub locked: Vec<LockedTeam>,
+}
+
+impl PlayersInput {
+    /// Parses either a plain list of players or an object with `players` and `locked`
+    pub fn from_json_str(s: &str) -> std::result::Result<Self, serde_json::Error> {
+        if s.trim_start().starts_with('{') {
+            serde_json::from_str(s)
+        } else {
+            Ok(Self {
+                players: serde_json::from_str(s)?,
+                locked: Vec::new(),
+            })
+        }
+    }
+
+    /// Like [`from_json_str`](Self::from_json_str), from already parsed json
+    pub fn from_json_value(
+        value: serde_json::Value,
+    ) -> std::result::Result<Self, serde_json::Error> {
+        if value.is_object() {
+            serde_json::from_value(value)
+        } else {
+            Ok(Self {
+                players: serde_json::from_value(value)?,
+                locked: Vec::new(),
+            })
+        }
+    }
 }
 
 impl NamedTeam {
     /// Creates a named team from a team and our lists, randomly generating a name
-    pub fn from_team_and_lists(
-		  team: StringTeam,
-        adjectives: &mut Vec<String>,
-        nouns: &mut Vec<String>,
+    pub fn from_team_and_lists<R: Rng>(
+        team: StringTeam,
+        adjectives: &[String],
+        nouns: &[String],
+        random: &mut R,
     ) -> Self {
         Self {
-            name: Self::generate_name(adjectives, nouns),
-				players: team
-		  }
+            name: Self::generate_name(adjectives, nouns, random),
+            players: team,
+            locked: false,
+            breakdown: None,
+        }
     }
 
     /// Generates a random team name from a random list of adjectives and nouns
-    pub fn generate_name(adjectives: &Vec<String>, nouns: &Vec<String>) -> String {
-        let mut random = rand::thread_rng();
-
+    pub fn generate_name<R: Rng>(
+        adjectives: &[String],
+        nouns: &[String],
+        random: &mut R,
+    ) -> String {
         let adjective_i = random.gen_range(0..adjectives.len());
         let adjective = adjectives[adjective_i].clone();
 
@@ -320,21 +903,325 @@ impl NamedTeam {
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+/// A player's request to play with someone, which the teams don't fulfill
+pub struct UnsatisfiedRequest {
+    pub player: String,
+    pub teammate: String,
+    /// Whether the teammate was required rather than just preferred
+    pub required: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+/// How far apart the strengths of teams are, in the unit of `rating.unit`
+pub struct StrengthSpread {
+    /// The strength of the weakest team
+    pub min: f64,
+    /// The strength of the strongest team
+    pub max: f64,
+    /// The standard deviation of the team strengths
+    pub stddev: f64,
+}
+
+impl StrengthSpread {
+    /// Computes the spread of the given team strengths, all 0 if there are none
+    pub fn of(strengths: &[f64]) -> Self {
+        if strengths.is_empty() {
+            return Self::default();
+        }
+
+        let count = strengths.len() as f64;
+        let mean = strengths.iter().sum::<f64>() / count;
+        let variance = strengths
+            .iter()
+            .map(|x| (x - mean) * (x - mean))
+            .sum::<f64>()
+            / count;
+
+        Self {
+            min: strengths.iter().copied().fold(f64::INFINITY, f64::min),
+            max: strengths.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
 pub struct Result {
     pub teams: Vec<NamedTeam>,
+    /// Players who didn't get a team, because the number of players isn't a multiple of three
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub substitutes: Vec<StringPlayer>,
+    /// Teammate requests the teams don't fulfill
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub unsatisfied_requests: Vec<UnsatisfiedRequest>,
+    /// The scoring config the result was made with
+    #[serde(default)]
+    pub config: ScoringConfig,
+    /// How the teams were picked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub solver: Option<SolverReport>,
+    /// The seed all randomness was drawn from, pass it again to reproduce the result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// The seed `name` renamed the teams with, if they were renamed. `seed` still reproduces
+    /// the teams themselves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub naming_seed: Option<u64>,
+    /// How far apart the strengths of the teams are
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub strength_spread: Option<StrengthSpread>,
+    /// How satisfied the players are with the teams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub summary: Option<Summary>,
 }
 
 impl Result {
     /// Scores the result.
     ///
     /// A higher score means we prefer the team compositions more.
-    pub fn score(&self, target_rank: f64) -> f64 {
+    pub fn score(&self, target_rank: f64, config: &ScoringConfig) -> f64 {
         let mut score = 0.0;
 
         for team in &self.teams {
-            score += team.players.score(target_rank);
+            score += team.players.score(target_rank, config);
         }
 
         score
     }
+
+    /// Returns the value of the config's [objective](crate::config::Objective) for the teams
+    pub fn objective_value(&self, target_rank: f64, config: &ScoringConfig) -> f64 {
+        let scores: Vec<f64> = self
+            .teams
+            .iter()
+            .map(|x| x.players.score(target_rank, config))
+            .collect();
+
+        config.objective_value(&scores, &self.team_strengths(config))
+    }
+
+    /// Explains the score of every team, see [`NamedTeam::breakdown`]
+    pub fn fill_breakdowns(&mut self, target_rank: f64, config: &ScoringConfig) {
+        for team in self.teams.iter_mut() {
+            team.breakdown = Some(team.players.breakdown(target_rank, config));
+        }
+    }
+
+    /// Returns the strength of every team, in the unit of `config.rating.unit`
+    pub fn team_strengths(&self, config: &ScoringConfig) -> Vec<f64> {
+        self.teams
+            .iter()
+            .map(|x| x.players.strength(&config.rating))
+            .collect()
+    }
+
+    /// Returns every preferred or required teammate request the teams don't fulfill
+    pub fn find_unsatisfied_requests(&self) -> Vec<UnsatisfiedRequest> {
+        let mut unsatisfied_requests = Vec::new();
+
+        let mut check = |player: &StringPlayer, team: Option<&StringTeam>| {
+            for (teammates, required) in [
+                (&player.preferred_teammates, false),
+                (&player.required_teammates, true),
+            ] {
+                for teammate in teammates {
+                    if !team.is_some_and(|x| x.in_team(teammate)) {
+                        unsatisfied_requests.push(UnsatisfiedRequest {
+                            player: player.id.clone(),
+                            teammate: teammate.clone(),
+                            required,
+                        });
+                    }
+                }
+            }
+        };
+
+        for team in &self.teams {
+            check(&team.players.goalie, Some(&team.players));
+            check(&team.players.midfield, Some(&team.players));
+            check(&team.players.forward, Some(&team.players));
+        }
+
+        for substitute in &self.substitutes {
+            check(substitute, None);
+        }
+
+        unsatisfied_requests
+    }
+
+    /// Returns the rank sum we would like each team to have, based on all players in the result
+    ///
+    /// It is in the unit of `config.rating.unit`.
+    pub fn target_rank(&self, config: &ScoringConfig) -> f64 {
+        if self.teams.is_empty() {
+            return 0.0;
+        }
+
+        let mut sum_of_ranks: f64 = 0.0;
+
+        for team in &self.teams {
+            for player in team.players.players() {
+                sum_of_ranks += player.strength(&config.rating);
+            }
+        }
+
+        for substitute in &self.substitutes {
+            sum_of_ranks += substitute.strength(&config.rating);
+        }
+
+        let player_count = self.teams.len() * 3 + self.substitutes.len();
+
+        sum_of_ranks / player_count as f64 * 3_f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> std::result::Result<RolePreferences, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    fn weights(preferences: RolePreferences) -> [f64; 3] {
+        Role::ALL.map(|x| preferences.weight(x))
+    }
+
+    #[test]
+    fn reads_role_lists() {
+        // Weights as goalie, midfield, forward
+        for (json, expected) in [
+            (r#"["Goalie", "Midfield", "Forward"]"#, [3.0, 2.0, 1.0]),
+            (r#"["Forward", "Goalie"]"#, [2.0, 1.0, 3.0]),
+            (r#"["Midfield"]"#, [1.0, 3.0, 1.0]),
+            (r#"["Goalie", "Fill"]"#, [3.0, 2.0, 2.0]),
+            (r#"["Fill", "Forward"]"#, [3.0, 3.0, 3.0]),
+            (r#"["Forward", "Forward", "Goalie"]"#, [2.0, 1.0, 3.0]),
+            (r#""Fill""#, [3.0, 3.0, 3.0]),
+        ] {
+            assert_eq!(weights(parse(json).unwrap()), expected, "{}", json);
+        }
+    }
+
+    #[test]
+    fn reads_role_weights() {
+        let preferences = parse(r#"{"Goalie": 0.9, "Forward": 0.3}"#).unwrap();
+
+        assert_eq!(weights(preferences), [3.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn rejects_invalid_role_preferences() {
+        for json in [
+            "[]",
+            r#"["Goalie", "Midfield", "Forward", "Fill"]"#,
+            r#"["Keeper"]"#,
+            r#""Goalie""#,
+            r#"{"Goalie": -1}"#,
+            r#"{"Goalie": 0}"#,
+        ] {
+            assert!(parse(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn choices_follow_the_weights() {
+        // Choices as goalie, midfield, forward
+        for (json, expected) in [
+            (r#"["Goalie", "Midfield", "Forward"]"#, [1, 2, 3]),
+            (r#"["Forward", "Goalie"]"#, [2, 3, 1]),
+            (r#"["Goalie"]"#, [1, 3, 3]),
+            (r#"["Midfield", "Fill"]"#, [2, 1, 2]),
+            (r#""Fill""#, [1, 1, 1]),
+            (r#"{"Goalie": 4, "Midfield": 3, "Forward": 1}"#, [1, 2, 3]),
+            (r#"{"Goalie": 4, "Midfield": 2}"#, [1, 3, 3]),
+        ] {
+            let preferences = parse(json).unwrap();
+
+            assert_eq!(
+                Role::ALL.map(|x| preferences.choice_of(x)),
+                expected,
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn role_preferences_round_trip() {
+        for json in [
+            r#"["Forward","Goalie","Midfield"]"#,
+            r#"{"Goalie":3.0,"Forward":1.5,"Midfield":0.0}"#,
+            r#"["Midfield", "Fill"]"#,
+            r#"["Goalie"]"#,
+            r#""Fill""#,
+        ] {
+            let preferences = parse(json).unwrap();
+            let written = serde_json::to_string(&preferences).unwrap();
+
+            assert_eq!(parse(&written).unwrap(), preferences, "{}", written);
+        }
+
+        // Anything which can be read from a list is written as one, anything else as weights
+        for (json, expected) in [
+            (
+                r#"["Midfield", "Forward"]"#,
+                r#"["Midfield","Forward","Goalie"]"#,
+            ),
+            (r#"["Midfield", "Fill"]"#, r#"["Midfield","Fill"]"#),
+            (r#"["Forward"]"#, r#"["Forward"]"#),
+            (r#"["Fill", "Goalie"]"#, r#""Fill""#),
+            (r#""Fill""#, r#""Fill""#),
+            (
+                r#"{"Goalie": 3, "Midfield": 3, "Forward": 1}"#,
+                r#"{"Goalie":3.0,"Midfield":3.0,"Forward":1.0}"#,
+            ),
+        ] {
+            let written = serde_json::to_string(&parse(json).unwrap()).unwrap();
+
+            assert_eq!(written, expected, "{}", json);
+        }
+    }
+
+    #[test]
+    fn breakdown_adds_up_to_the_score() {
+        let player = |id: &str, rank: u8, roles: &[Role]| StringPlayer {
+            id: id.to_string(),
+            rank: Rank::new(rank).unwrap(),
+            role_preferences: RolePreferences::from_ranking(roles),
+            ..Default::default()
+        };
+
+        let mut goalie = player("a", 4, &[Role::Goalie]);
+        let mut midfield = player("b", 6, &[Role::Forward, Role::Midfield]);
+        let forward = player("c", 8, &[Role::Midfield]);
+
+        goalie.blacklisted_players = vec!["c".to_string()];
+        midfield.preferred_teammates = vec!["a".to_string()];
+
+        let team = StringTeam::new(goalie, midfield, forward);
+        let config = ScoringConfig::default();
+
+        // 18 is 2 off the target, so the rank adds part of its weight
+        let breakdown = team.breakdown(20.0, &config);
+
+        assert_eq!(breakdown.total, team.score(20.0, &config));
+
+        let roles: f64 = breakdown.roles.iter().map(|x| x.score).sum();
+        let parts = roles + breakdown.rank_score - breakdown.blacklist_penalty
+            + breakdown.preferred_teammate_bonus;
+
+        assert!((parts - breakdown.total).abs() < 1e-9);
+        assert_eq!(roles, 3.0 + 2.0 + 1.0);
+        assert_eq!(breakdown.rank_score, 6.0);
+        assert_eq!(breakdown.blacklist_penalty, 20.0);
+        assert_eq!(breakdown.preferred_teammate_bonus, 5.0);
+    }
 }
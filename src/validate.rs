@@ -0,0 +1,672 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{BlacklistMode, ScoringConfig};
+use crate::pairer::{
+    intern_players, locked_team_overlap, oversized_groups, players_without_allowed_team,
+};
+use crate::rank::Rank;
+use crate::scoring::{PlayerLike, TeamLike};
+use crate::types::{PlayersInput, Role, StringPlayer};
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+/// A problem with a players file
+pub struct Issue {
+    /// The line of the field, or of the player if the problem isn't about a single field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// The id of the player, if the problem is about a single player
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub player: Option<String>,
+    /// The field of the player with the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+
+        if let Some(ref player) = self.player {
+            write!(f, "player {}: ", player)?;
+        }
+
+        if let Some(ref field) = self.field {
+            write!(f, "{}: ", field)?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Checks a players file (a list of players, or an object with `players` and `locked`) for
+/// problems which pairing would silently work around, or which make pairing impossible:
+///
+/// - Ids used by more than one player
+/// - Players who are also in a locked team, ignoring case like pairing does
+/// - Players who are in more than one locked team
+/// - Blacklisted, preferred or required teammates who aren't registered players
+/// - Players who list themselves
+/// - Ranks which aren't [valid ranks](crate::rank::Rank)
+/// - Roles listed more than once
+/// - Constraints which can't all be kept, e.g. more than three players who have to play
+///   together, or not enough players for a role
+///
+/// Fails if the file can't be deserialized at all.
+pub fn validate_players_file(
+    source: &str,
+    config: &ScoringConfig,
+) -> Result<Vec<Issue>, serde_json::Error> {
+    let mut value: Value = serde_json::from_str(source)?;
+
+    let mut rank_problems = Vec::new();
+
+    let raw_players = match &mut value {
+        Value::Object(object) => object.get_mut("players"),
+        value => Some(value),
+    };
+
+    // Read ranks leniently, so every bad one is reported rather than failing on the first
+    if let Some(Value::Array(raw_players)) = raw_players {
+        for (i, player) in raw_players.iter_mut().enumerate() {
+            let Some(rank) = player.get_mut("rank") else {
+                continue;
+            };
+
+            if let Some(message) = rank_problem(rank) {
+                rank_problems.push((i, message));
+
+                // Any valid rank does, so the rest of the player can still be checked
+                *rank = Value::from(0);
+            }
+        }
+    }
+
+    let input = PlayersInput::from_json_value(value.clone())?;
+
+    let raw_players = match value {
+        Value::Object(mut object) => object.remove("players"),
+        value => Some(value),
+    };
+    let raw_players = match raw_players {
+        Some(Value::Array(players)) => players,
+        _ => Vec::new(),
+    };
+
+    let lines = locate_players(source);
+
+    let players = &input.players;
+
+    let issue = |index: usize, field: Option<&str>, message: String| {
+        let player_lines = lines.get(index);
+
+        let field_line = field.and_then(|field| {
+            player_lines?
+                .fields
+                .iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, line)| *line)
+        });
+
+        Issue {
+            line: field_line.or(player_lines.map(|x| x.line)),
+            player: Some(players[index].id.clone()),
+            field: field.map(|x| x.to_string()),
+            message,
+        }
+    };
+
+    let mut issues = Vec::new();
+
+    let locked: Vec<&StringPlayer> = input
+        .locked
+        .iter()
+        .flat_map(|x| x.players.players())
+        .collect();
+
+    let is_locked = |name: &String| locked.iter().any(|x| x.is(name));
+
+    let is_registered = |name: &String| players.iter().any(|x| x.is(name)) || is_locked(name);
+
+    for (i, player) in players.iter().enumerate() {
+        if let Some(other) = players[..i]
+            .iter()
+            .position(|x| x.id.to_lowercase() == player.id.to_lowercase())
+        {
+            let other_line = lines.get(other).map(|x| x.line);
+
+            let message = match other_line {
+                Some(line) => format!("the id is also used by the player on line {}", line),
+                None => format!("the id is also used by player {}", other + 1),
+            };
+
+            issues.push(issue(i, Some("id"), message));
+        }
+
+        if is_locked(&player.id) {
+            issues.push(issue(
+                i,
+                Some("id"),
+                "the player is also in a locked team, so they only play there".into(),
+            ));
+        }
+
+        for (_, message) in rank_problems.iter().filter(|x| x.0 == i) {
+            issues.push(issue(i, Some("rank"), message.clone()));
+        }
+
+        if let Some(roles) = raw_players
+            .get(i)
+            .and_then(|x| x.get("role_preferences"))
+            .and_then(|x| x.as_array())
+        {
+            for (j, role) in roles.iter().enumerate() {
+                if roles[..j].contains(role) {
+                    issues.push(issue(
+                        i,
+                        Some("role_preferences"),
+                        format!("{} is listed more than once", role),
+                    ));
+                }
+            }
+        }
+
+        for (field, names) in [
+            ("blacklisted_players", &player.blacklisted_players),
+            ("preferred_teammates", &player.preferred_teammates),
+            ("required_teammates", &player.required_teammates),
+        ] {
+            for name in names {
+                if name.to_lowercase() == player.id.to_lowercase() {
+                    issues.push(issue(i, Some(field), "the player lists themselves".into()));
+                } else if !is_registered(name) {
+                    issues.push(issue(
+                        i,
+                        Some(field),
+                        format!("{} is not a registered player", name),
+                    ));
+                }
+            }
+        }
+
+        if !Role::ALL.iter().any(|x| player.may_play(*x)) {
+            let field = if player.locked_role.is_some() {
+                "locked_role"
+            } else {
+                "banned_roles"
+            };
+
+            issues.push(issue(
+                i,
+                Some(field),
+                "the player can't play any role".into(),
+            ));
+        }
+    }
+
+    for id in locked_team_overlap(&input.locked) {
+        issues.push(Issue {
+            player: Some(id),
+            message: "the player is in more than one locked team, but can only play on one".into(),
+            ..Default::default()
+        });
+    }
+
+    // Players in locked teams never take part in pairing, so only the others have to fit
+    let free: Vec<usize> = (0..players.len())
+        .filter(|x| !is_locked(&players[*x].id))
+        .collect();
+    let free_players: Vec<StringPlayer> = free.iter().map(|x| players[*x].clone()).collect();
+
+    let (_, interned) = intern_players(&free_players, false);
+
+    for (i, player) in interned.iter().enumerate() {
+        for required in player.required_teammates.iter() {
+            let Some(j) = interned.iter().position(|x| x.id == *required) else {
+                continue;
+            };
+
+            // Only once if both say they have to play with eachother
+            let reported_by_other = j < i && interned[j].required_teammates.contains(&player.id);
+
+            if !reported_by_other
+                && (player.blacklisted_players.contains(&interned[j].id)
+                    || interned[j].blacklisted_players.contains(&player.id))
+            {
+                issues.push(issue(
+                    free[i],
+                    Some("required_teammates"),
+                    format!(
+                        "the player has to play with {}, but one of them blacklisted the other",
+                        free_players[j].id
+                    ),
+                ));
+            }
+        }
+    }
+
+    for members in oversized_groups(&interned) {
+        let ids: Vec<&str> = members
+            .iter()
+            .map(|x| free_players[*x].id.as_str())
+            .collect();
+
+        issues.push(Issue {
+            line: lines.get(free[members[0]]).map(|x| x.line),
+            message: format!(
+                "{} have to play together, but a team only has 3 players",
+                ids.join(", ")
+            ),
+            ..Default::default()
+        });
+    }
+
+    if config.blacklist_mode == BlacklistMode::Hard {
+        let without_team = players_without_allowed_team(&interned);
+
+        // As many as there are substitutes can just sit out
+        if without_team.len() > interned.len() % 3 {
+            for i in without_team {
+                issues.push(issue(
+                    free[i],
+                    Some("blacklisted_players"),
+                    "blacklists are hard, but any team with the player breaks a blacklist".into(),
+                ));
+            }
+        }
+    }
+
+    if config.deviation_below_a_rank() {
+        issues.push(Issue {
+            message: format!(
+                "allowed_rank_deviation is {}, less than one rank's worth of rating ({}), so hardly any team gets a rank score",
+                config.allowed_deviation(),
+                config.rating.in_unit(1.0)
+            ),
+            ..Default::default()
+        });
+    }
+
+    for rule in config.rules.iter() {
+        if !players
+            .iter()
+            .any(|x| x.attributes.contains_key(&rule.attribute))
+        {
+            issues.push(Issue {
+                message: format!(
+                    "the scoring rule '{}' looks at '{}', but no player has that attribute",
+                    rule.name, rule.attribute
+                ),
+                ..Default::default()
+            });
+        }
+    }
+
+    let team_count = free.len() / 3;
+
+    for role in Role::ALL {
+        let count = interned.iter().filter(|x| x.may_play(role)).count();
+
+        if count < team_count {
+            issues.push(Issue {
+                message: format!(
+                    "only {} players may play {:?}, but {} teams need one",
+                    count, role, team_count
+                ),
+                ..Default::default()
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Returns what's wrong with a rank, if it isn't a valid one
+fn rank_problem(rank: &Value) -> Option<String> {
+    match rank {
+        Value::String(name) => name.parse::<Rank>().err(),
+        Value::Number(number) => number
+            .as_u64()
+            .and_then(|x| u8::try_from(x).ok())
+            .and_then(Rank::new)
+            .is_none()
+            .then(|| format!("{} is not between 0 and {}", number, Rank::MAX.value())),
+        _ => Some(format!(
+            "{} is not a rank, expected a number between 0 and {} or the name of a rank",
+            rank,
+            Rank::MAX.value()
+        )),
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+/// The lines where a player and its fields start
+struct PlayerLines {
+    line: usize,
+    fields: Vec<(String, usize)>,
+}
+
+/// Finds the lines of the players in a players file.
+///
+/// serde_json doesn't tell us where values are, so this walks over the json itself. Only
+/// meant for files which deserialize fine, anything unexpected just stops the walk.
+fn locate_players(source: &str) -> Vec<PlayerLines> {
+    let mut scanner = Scanner {
+        bytes: source.as_bytes(),
+        pos: 0,
+        line: 1,
+    };
+
+    match scanner.peek() {
+        Some(b'[') => scanner.players(),
+        Some(b'{') => {
+            scanner.bump();
+
+            while let Some(b'"') = scanner.peek() {
+                let key = scanner.string();
+
+                if scanner.peek() != Some(b':') {
+                    break;
+                }
+                scanner.bump();
+
+                if key == "players" && scanner.peek() == Some(b'[') {
+                    return scanner.players();
+                }
+
+                scanner.skip_value();
+
+                if scanner.peek() == Some(b',') {
+                    scanner.bump();
+                }
+            }
+
+            Vec::new()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Walks over json, keeping track of the line
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl Scanner<'_> {
+    /// Skips whitespace and returns the next byte without taking it
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(byte) = self.bytes.get(self.pos) {
+            if !byte.is_ascii_whitespace() {
+                return Some(*byte);
+            }
+
+            self.bump();
+        }
+
+        None
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+
+        self.pos += 1;
+
+        if byte == b'\n' {
+            self.line += 1;
+        }
+
+        Some(byte)
+    }
+
+    /// Takes a string, returning its raw contents
+    fn string(&mut self) -> String {
+        self.bump();
+
+        let start = self.pos;
+
+        loop {
+            match self.bump() {
+                Some(b'\\') => {
+                    self.bump();
+                }
+                Some(b'"') => break,
+                Some(_) => {}
+                None => return String::new(),
+            }
+        }
+
+        String::from_utf8_lossy(&self.bytes[start..self.pos - 1]).into_owned()
+    }
+
+    fn skip_value(&mut self) {
+        match self.peek() {
+            Some(b'"') => {
+                self.string();
+            }
+            Some(b'[' | b'{') => {
+                let mut depth = 0;
+
+                while let Some(byte) = self.peek() {
+                    match byte {
+                        b'"' => {
+                            self.string();
+                            continue;
+                        }
+                        b'[' | b'{' => depth += 1,
+                        b']' | b'}' => depth -= 1,
+                        _ => {}
+                    }
+
+                    self.bump();
+
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            _ => {
+                while let Some(byte) = self.peek() {
+                    if matches!(byte, b',' | b']' | b'}') {
+                        break;
+                    }
+
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    /// Takes a list of players
+    fn players(&mut self) -> Vec<PlayerLines> {
+        let mut players = Vec::new();
+
+        self.bump();
+
+        loop {
+            match self.peek() {
+                Some(b'{') => {
+                    let line = self.line;
+                    let fields = self.fields();
+
+                    players.push(PlayerLines { line, fields });
+                }
+                Some(b',') => {
+                    self.bump();
+                }
+                Some(b']') | None => break,
+                Some(_) => self.skip_value(),
+            }
+        }
+
+        players
+    }
+
+    /// Takes an object, returning the line of each of its keys
+    fn fields(&mut self) -> Vec<(String, usize)> {
+        let mut fields = Vec::new();
+
+        self.bump();
+
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    let line = self.line;
+                    let key = self.string();
+
+                    if self.peek() != Some(b':') {
+                        break;
+                    }
+                    self.bump();
+
+                    self.skip_value();
+
+                    fields.push((key, line));
+                }
+                Some(b',') => {
+                    self.bump();
+                }
+                Some(b'}') => {
+                    self.bump();
+                    break;
+                }
+                _ => break,
+            }
+        }
+
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The fields of a player with their lines, for comparing
+    fn fields(player: &PlayerLines) -> Vec<(&str, usize)> {
+        player
+            .fields
+            .iter()
+            .map(|(name, line)| (name.as_str(), *line))
+            .collect()
+    }
+
+    #[test]
+    fn locates_players_in_a_list() {
+        let source = r#"[
+  {
+    "id": "a",
+    "rank": 3,
+    "role_preferences": ["Goalie", "Fill"]
+  },
+  {"id": "b", "rank": "Omega",
+   "blacklisted_players": [
+     "a"
+   ],
+   "role_preferences": {"Forward": 1}}
+]"#;
+
+        let players = locate_players(source);
+
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].line, 2);
+        assert_eq!(
+            fields(&players[0]),
+            [("id", 3), ("rank", 4), ("role_preferences", 5)]
+        );
+        assert_eq!(players[1].line, 7);
+        assert_eq!(
+            fields(&players[1]),
+            [
+                ("id", 7),
+                ("rank", 7),
+                ("blacklisted_players", 8),
+                ("role_preferences", 11)
+            ]
+        );
+    }
+
+    #[test]
+    fn locates_players_after_locked_teams() {
+        let source = r#"{
+  "locked": [
+    {"players": {"goalie": {"id": "x", "rank": 1, "role_preferences": "Fill"}}}
+  ],
+  "players": [
+    {
+      "id": "a",
+      "rank": 3
+    }
+  ]
+}"#;
+
+        let players = locate_players(source);
+
+        assert_eq!(players.len(), 1);
+        assert_eq!(players[0].line, 6);
+        assert_eq!(fields(&players[0]), [("id", 7), ("rank", 8)]);
+    }
+
+    #[test]
+    fn skips_escaped_quotes_and_brackets_in_strings() {
+        let source = "[\n  {\"id\": \"a \\\" ] } [ {\",\n   \"rank\": 3},\n  {\"id\": \"\\\\\",\n   \"rank\": 4}\n]";
+
+        let players = locate_players(source);
+
+        assert_eq!(players.len(), 2);
+        assert_eq!(fields(&players[0]), [("id", 2), ("rank", 3)]);
+        assert_eq!(fields(&players[1]), [("id", 4), ("rank", 5)]);
+    }
+
+    #[test]
+    fn reports_every_invalid_rank_with_its_line() {
+        let source = r#"[
+  {"id": "a", "rank": 12, "role_preferences": "Fill"},
+  {"id": "b", "rank": "Mid Gold", "role_preferences": "Fill"},
+  {"id": "c", "rank": "Low Diamond", "role_preferences": "Fill"}
+]"#;
+
+        let issues = validate_players_file(source, &ScoringConfig::default()).unwrap();
+
+        let ranks: Vec<(Option<usize>, Option<&str>)> = issues
+            .iter()
+            .filter(|x| x.field.as_deref() == Some("rank"))
+            .map(|x| (x.line, x.player.as_deref()))
+            .collect();
+
+        assert_eq!(ranks, [(Some(2), Some("a")), (Some(3), Some("b"))]);
+    }
+
+    #[test]
+    fn reports_players_in_more_than_one_locked_team() {
+        let source = r#"{
+  "locked": [
+    {"players": {
+      "goalie": {"id": "X", "rank": 1, "role_preferences": "Fill"},
+      "midfield": {"id": "y", "rank": 1, "role_preferences": "Fill"},
+      "forward": {"id": "z", "rank": 1, "role_preferences": "Fill"}
+    }},
+    {"players": {
+      "goalie": {"id": "u", "rank": 1, "role_preferences": "Fill"},
+      "midfield": {"id": "v", "rank": 1, "role_preferences": "Fill"},
+      "forward": {"id": "x", "rank": 1, "role_preferences": "Fill"}
+    }}
+  ],
+  "players": []
+}"#;
+
+        let issues = validate_players_file(source, &ScoringConfig::default()).unwrap();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].player.as_deref(), Some("x"));
+    }
+}